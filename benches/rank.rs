@@ -0,0 +1,105 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use how::{
+    context::Environment,
+    db::Entry,
+    rank::{self, Algorithm, Bm25Index, Options, Searcher},
+};
+
+const TOOLS: [&str; 10] = [
+    "git",
+    "docker",
+    "kubectl",
+    "cargo",
+    "systemctl",
+    "tar",
+    "find",
+    "ssh",
+    "npm",
+    "psql",
+];
+const ACTIONS: [&str; 10] = [
+    "status", "list", "restart", "logs", "build", "remove", "create", "inspect", "sync", "show",
+];
+const OBJECTS: [&str; 5] = ["containers", "branches", "services", "files", "databases"];
+
+/// A library about as large as a shared team one.
+fn entries() -> Vec<Entry> {
+    (0..5000)
+        .map(|i| {
+            let (tool, action, object) = (
+                TOOLS[i % TOOLS.len()],
+                ACTIONS[i / TOOLS.len() % ACTIONS.len()],
+                OBJECTS[i / 7 % OBJECTS.len()],
+            );
+            Entry::new(
+                format!("{tool} {action} {object} {i}"),
+                format!("{tool} {action} --{object} [#arg{i}]"),
+                format!("{action} the {object} with {tool}, variant {i}"),
+            )
+        })
+        .collect()
+}
+
+fn options(algorithm: Algorithm) -> Options {
+    Options {
+        algorithm,
+        ..Options::default()
+    }
+}
+
+fn rank(c: &mut Criterion) {
+    let (entries, environment) = (entries(), Environment::default());
+    let index = Bm25Index::new(&entries);
+    let mut group = c.benchmark_group("rank");
+    for algorithm in [Algorithm::Trigram, Algorithm::Subsequence] {
+        let options = options(algorithm);
+        for query in [
+            "g",
+            "docker logs",
+            "!git code:^kubectl",
+            "how do I restart services",
+        ] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{algorithm:?}"), query),
+                query,
+                |b, query| b.iter(|| rank::rank(&options, &environment, query, &entries, &index)),
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Ranks every prefix of a query, as if it was typed.
+fn typing(c: &mut Criterion) {
+    let (entries, environment) = (entries(), Environment::default());
+    let index = Bm25Index::new(&entries);
+    let query = "kubectl restart services";
+    let mut group = c.benchmark_group("typing");
+    for algorithm in [Algorithm::Trigram, Algorithm::Subsequence] {
+        let options = options(algorithm);
+        group.bench_function(format!("{algorithm:?}"), |b| {
+            b.iter_batched_ref(
+                || Searcher::new(options.clone(), &environment, &entries, &index),
+                |searcher| {
+                    for end in 1..=query.len() {
+                        searcher.rank(&query[..end], || false);
+                    }
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn haystacks(c: &mut Criterion) {
+    let (entries, environment) = (entries(), Environment::default());
+    let index = Bm25Index::new(&entries);
+    c.bench_function("haystacks", |b| {
+        b.iter(|| Searcher::new(Options::default(), &environment, &entries, &index))
+    });
+    c.bench_function("bm25_index", |b| b.iter(|| Bm25Index::new(&entries)));
+}
+
+criterion_group!(benches, rank, typing, haystacks);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+# https://github.com/junegunn/fzf/blob/master/shell/key-bindings.bash
+# Unlike fzf's file widget, the selection is a command, so it's inserted into
+# READLINE_LINE as it is instead of being quoted with printf %q.
+
+__how_select__() {
+  how search "$@" < /dev/tty
+}
+
+__how_widget__() {
+  local selected
+  selected="$(__how_select__)" || return
+  READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}${selected}${READLINE_LINE:$READLINE_POINT}"
+  READLINE_POINT=$((READLINE_POINT + ${#selected}))
+}
@@ -0,0 +1,9 @@
+# https://github.com/junegunn/fzf/blob/master/shell/key-bindings.fish
+# Template inputs are quoted for fish by `--quoting fish`, so the command is
+# inserted as it is.
+
+function how-widget -d "Insert a command from how"
+  set -l result (how search --quoting fish < /dev/tty | string collect)
+  and commandline -i -- $result
+  commandline -f repaint
+end
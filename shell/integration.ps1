@@ -0,0 +1,10 @@
+# Add to your profile with:
+# how init pwsh | Out-String | Invoke-Expression
+
+function Invoke-HowWidget {
+  $result = (how search | Out-String).TrimEnd("`r", "`n")
+  [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+  if ($LASTEXITCODE -eq 0 -and $result) {
+    [Microsoft.PowerShell.PSConsoleReadLine]::Insert($result)
+  }
+}
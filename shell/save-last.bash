@@ -0,0 +1,7 @@
+__how_save_last_widget__() {
+  local last
+  last="$(HISTTIMEFORMAT= builtin history 1)"
+  # remove the history number
+  last="${last#*[0-9]  }"
+  how add --code="${last}" < /dev/tty
+}
@@ -0,0 +1,4 @@
+function how-save-last-widget -d "Save the previous command to how"
+  how add --code=$history[1] < /dev/tty
+  commandline -f repaint
+end
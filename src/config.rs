@@ -0,0 +1,84 @@
+//! User configuration, read from `how-config.toml` in the config directory.
+
+use std::{fs, io::ErrorKind};
+
+use color_eyre::eyre::{Context, Result};
+use serde::Deserialize;
+
+use crate::rank::{self, Algorithm, Boosts, Scope, Synonyms, Weights};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Algorithm used to rank entries when searching.
+    pub ranker: Algorithm,
+    /// How much matching each field of an entry is worth.
+    pub weights: Weights,
+    /// How much entries that are relevant where `how` was launched are
+    /// boosted.
+    pub boosts: Boosts,
+    /// Which entries are searched at first, until it's toggled.
+    pub scope: Scope,
+    /// Groups of words that mean the same thing, on top of built-in ones
+    /// like `remove`, `delete` and `rm`.
+    pub synonyms: Vec<Vec<String>>,
+    /// Minimum score of entries shown when searching.
+    pub min_score: f32,
+}
+
+impl Config {
+    pub fn rank_options(&self) -> rank::Options {
+        rank::Options {
+            algorithm: self.ranker,
+            weights: self.weights,
+            boosts: self.boosts,
+            scope: self.scope,
+            synonyms: Synonyms::default().with_groups(&self.synonyms),
+            min_score: self.min_score,
+        }
+    }
+
+    /// Loads the config, using the defaults if there is no config file.
+    pub fn load() -> Result<Self> {
+        let Some(dir) = dirs::config_dir() else {
+            return Ok(Self::default());
+        };
+        let path = dir.join("how-config.toml");
+        match fs::read_to_string(&path) {
+            Ok(s) => {
+                toml::from_str(&s).with_context(|| format!("invalid config in {}", path.display()))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("unable to read {}", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::rank::{Algorithm, Weights};
+
+    #[test]
+    fn parses() {
+        let config = toml::from_str::<Config>("ranker = \"subsequence\"").unwrap();
+        assert_eq!(config.ranker, Algorithm::Subsequence);
+
+        let config = toml::from_str::<Config>("").unwrap();
+        assert_eq!(config.ranker, Algorithm::Trigram);
+        assert_eq!(config.weights, Weights::default());
+
+        let config = toml::from_str::<Config>("min_score = 0.5\n[weights]\ncode = 3.0").unwrap();
+        assert_eq!(config.min_score, 0.5);
+        assert_eq!(config.weights.code, 3.0);
+        assert_eq!(config.weights.title, Weights::default().title);
+
+        let config = toml::from_str::<Config>("[boosts]\ncontext = 2.0").unwrap();
+        assert_eq!(config.boosts.context, 2.0);
+
+        let config = toml::from_str::<Config>("synonyms = [[\"deploy\", \"ship\"]]").unwrap();
+        assert_eq!(config.rank_options().synonyms.of("ship"), ["deploy"]);
+
+        assert!(toml::from_str::<Config>("rankr = \"trigram\"").is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Where `how` was launched, to boost entries that are relevant there.
+//!
+//! Entries can declare contexts in the database, and are boosted when any
+//! of them is active:
+//!
+//! ```toml
+//! contexts = [{ file = "Cargo.toml" }, { remote = "github.com/acme/*" }, { dir = "~/work" }]
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Context {
+    /// A glob matching the name of a file in the current directory, or in
+    /// a directory above it inside the same repository.
+    File(String),
+    /// A glob matching a git remote of the current repository, like
+    /// `github.com/owner/repo`.
+    Remote(String),
+    /// A directory that contains the current directory, where `~` is the
+    /// home directory.
+    Dir(String),
+}
+
+/// What contexts are checked against.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    dir: PathBuf,
+    /// Root of the repository the current directory is in.
+    root: Option<PathBuf>,
+    /// Names of the files in the current directory, and in the directories
+    /// above it up to the repository root.
+    files: Vec<String>,
+    /// Remotes of the current repository, normalized by [`normalize_remote`].
+    remotes: Vec<String>,
+}
+
+impl Environment {
+    /// Detects the environment of the current directory, or an empty one
+    /// where no context is active if it's unavailable.
+    pub fn detect() -> Self {
+        env::current_dir().map(Self::at).unwrap_or_default()
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        let root = dir.ancestors().find(|d| d.join(".git").exists());
+        let dirs = match root {
+            Some(root) => dir
+                .ancestors()
+                .take_while(|d| *d != root)
+                .chain([root])
+                .collect(),
+            None => vec![dir.as_path()],
+        };
+        let files = dirs
+            .iter()
+            .filter_map(|d| fs::read_dir(d).ok())
+            .flatten()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .collect();
+        let remotes = root.map(git_remotes).unwrap_or_default();
+
+        Self {
+            files,
+            remotes,
+            root: root.map(Path::to_owned),
+            dir,
+        }
+    }
+
+    /// The current directory, which is empty if it's unavailable.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether a directory is the current one, or in the same repository.
+    pub fn is_nearby(&self, dir: &Path) -> bool {
+        !self.dir.as_os_str().is_empty()
+            && (dir == self.dir || self.root.as_ref().is_some_and(|root| dir.starts_with(root)))
+    }
+
+    pub fn is_active(&self, context: &Context) -> bool {
+        match context {
+            Context::File(pattern) => self.files.iter().any(|f| glob_match(pattern, f)),
+            Context::Remote(pattern) => self
+                .remotes
+                .iter()
+                .any(|r| glob_match(&normalize_remote(pattern), r)),
+            Context::Dir(dir) => {
+                let dir = match dir.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+                    None if dir == "~" => dirs::home_dir(),
+                    None => Some(PathBuf::from(dir)),
+                };
+                dir.is_some_and(|dir| !self.dir.as_os_str().is_empty() && self.dir.starts_with(dir))
+            }
+        }
+    }
+}
+
+/// Reads the remote urls of a repository from its git config.
+fn git_remotes(root: &Path) -> Vec<String> {
+    let Ok(config) = fs::read_to_string(root.join(".git").join("config")) else {
+        return Vec::new();
+    };
+    let mut remotes = Vec::new();
+    let mut in_remote = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_remote = line.starts_with("[remote ");
+        } else if let (true, Some((key, value))) = (in_remote, line.split_once('=')) {
+            if key.trim() == "url" {
+                remotes.push(normalize_remote(value.trim()));
+            }
+        }
+    }
+    remotes
+}
+
+/// Turns the different forms of a remote url into `host/path`, so
+/// `git@github.com:owner/repo.git` and `https://github.com/owner/repo`
+/// are the same.
+fn normalize_remote(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url = url.split_once('@').map_or(url, |(_, rest)| rest);
+    let url = url.strip_suffix('/').unwrap_or(url);
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    url.replacen(':', "/", 1)
+}
+
+/// Matches text against a glob, where `*` matches any characters and `?`
+/// matches one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (
+        pattern.chars().collect::<Vec<_>>(),
+        text.chars().collect::<Vec<_>>(),
+    );
+    let (mut p, mut t) = (0, 0);
+    // where the last `*` was, and where in the text it started matching
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // let the last `*` match one more character
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{glob_match, normalize_remote, Context, Environment};
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("Cargo.toml", "Cargo.toml"));
+        assert!(glob_match("*.csproj", "app.csproj"));
+        assert!(glob_match("docker-compose.y?ml", "docker-compose.yaml"));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("*.csproj", "app.csproj.bak"));
+        assert!(!glob_match("package.json", "Cargo.toml"));
+    }
+
+    #[test]
+    fn remotes() {
+        for url in [
+            "git@github.com:acme/web.git",
+            "https://github.com/acme/web",
+            "ssh://git@github.com/acme/web.git",
+        ] {
+            assert_eq!(normalize_remote(url), "github.com/acme/web");
+        }
+    }
+
+    #[test]
+    fn active() {
+        let environment = Environment {
+            dir: PathBuf::from("/work/web/src"),
+            root: Some(PathBuf::from("/work/web")),
+            files: vec!["package.json".into(), "index.ts".into()],
+            remotes: vec!["github.com/acme/web".into()],
+        };
+        let active = |context| environment.is_active(&context);
+        assert!(active(Context::File("package.json".into())));
+        assert!(!active(Context::File("Cargo.toml".into())));
+        assert!(active(Context::Remote("github.com/acme/*".into())));
+        assert!(active(Context::Remote(
+            "git@github.com:acme/web.git".into()
+        )));
+        assert!(!active(Context::Remote("gitlab.com/*".into())));
+        assert!(active(Context::Dir("/work".into())));
+        assert!(!active(Context::Dir("/work/api".into())));
+
+        assert!(!Environment::default().is_active(&Context::Dir("/".into())));
+    }
+
+    #[test]
+    fn nearby() {
+        let environment = Environment {
+            dir: PathBuf::from("/work/web/src"),
+            root: Some(PathBuf::from("/work/web")),
+            ..Environment::default()
+        };
+        assert!(environment.is_nearby(Path::new("/work/web/src")));
+        assert!(environment.is_nearby(Path::new("/work/web/docs")));
+        assert!(!environment.is_nearby(Path::new("/work/api")));
+
+        let environment = Environment {
+            root: None,
+            ..environment
+        };
+        assert!(!environment.is_nearby(Path::new("/work/web/docs")));
+        assert!(!Environment::default().is_nearby(Path::new("")));
+    }
+}
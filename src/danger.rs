@@ -0,0 +1,77 @@
+//! Detecting commands that are likely to be destructive, so they can be
+//! confirmed before being executed.
+
+/// Whether any of the commands in a command line match a known
+/// destructive pattern, like `rm -rf`, a force push, `DROP TABLE` or
+/// `kubectl delete`.
+///
+/// This is a best effort check of the words in each command, and doesn't
+/// understand quoting or variables.
+pub fn is_dangerous(command: &str) -> bool {
+    let lower = command.to_lowercase();
+    if lower.contains("drop table") || lower.contains("drop database") {
+        return true;
+    }
+
+    lower
+        .split([';', '&', '|', '\n', '(', ')', '`'])
+        .any(|segment| {
+            let words = segment.split_whitespace().collect::<Vec<_>>();
+            words.iter().enumerate().any(|(i, &word)| {
+                let args = &words[i + 1..];
+                match word {
+                    "rm" => is_forced_recursive_rm(args),
+                    "git" => args.contains(&"push") && args.iter().any(|a| is_force(a)),
+                    "kubectl" => args.contains(&"delete"),
+                    _ => false,
+                }
+            })
+        })
+}
+
+/// Whether `rm` arguments have both a recursive and a force flag.
+fn is_forced_recursive_rm(args: &[&str]) -> bool {
+    let has_short = |flag| {
+        args.iter()
+            .any(|a| !a.starts_with("--") && a.starts_with('-') && a.contains(flag))
+    };
+    let recursive = has_short('r') || args.contains(&"--recursive");
+    let force = has_short('f') || args.contains(&"--force");
+    recursive && force
+}
+
+fn is_force(arg: &str) -> bool {
+    arg == "-f" || arg.starts_with("--force") || (arg.starts_with('+') && arg.len() > 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_dangerous;
+
+    #[test]
+    fn detects() {
+        for command in [
+            "rm -rf ./build",
+            "sudo rm -r -f /tmp/x",
+            "cd .. && rm --recursive --force target",
+            "git push --force origin main",
+            "git push origin +main",
+            "psql -c 'DROP TABLE users'",
+            "kubectl -n prod delete pod web",
+            "kubectl delete -f deploy.yaml",
+        ] {
+            assert!(is_dangerous(command), "{command} should be dangerous");
+        }
+
+        for command in [
+            "rm file.txt",
+            "rm -r empty_dir",
+            "git push origin main",
+            "git fetch --force",
+            "kubectl get pods",
+            "echo rmdir -rf",
+        ] {
+            assert!(!is_dangerous(command), "{command} should not be dangerous");
+        }
+    }
+}
@@ -2,7 +2,8 @@ use std::{
     cmp,
     fs::File,
     io::{Read, Seek, Write},
-    iter,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use color_eyre::eyre::{Context, Result};
@@ -13,12 +14,62 @@ use ratatui::{
 use ratatui_macros::vertical;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    context,
+    rank::{Bm25Index, Haystack, Positions, Text},
+    widgets::highlight,
+};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Entry {
     pub title: String,
     pub code: String,
     pub description: String,
     pub used: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Whether the command should always be confirmed before executing,
+    /// even if it doesn't look destructive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dangerous: bool,
+    /// Whether the code uses the [template](crate::template) syntax, whose
+    /// inputs are filled in when the entry is selected. Other code is used
+    /// as it is.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub template: bool,
+    /// Where the entry is relevant, to rank it higher there.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contexts: Vec<context::Context>,
+    /// Most recent uses of the entry, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Usage>,
+}
+
+/// Where and when an entry was used.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub dir: PathBuf,
+    /// Seconds since the unix epoch.
+    pub time: u64,
+}
+
+impl Usage {
+    pub fn now(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            time: unix_time(),
+        }
+    }
+}
+
+/// Number of uses kept in the history of each entry.
+const MAX_HISTORY: usize = 20;
+
+/// Seconds since the unix epoch.
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
 }
 
 impl Entry {
@@ -32,38 +83,69 @@ impl Entry {
             description: description.into(),
             code: answer.into(),
             used: 0,
+            tags: Vec::new(),
+            dangerous: false,
+            template: false,
+            contexts: Vec::new(),
+            history: Vec::new(),
         }
     }
 
-    /// Converts the entry into one string that should be searched
-    /// for fuzzy finding.
-    pub fn to_haystack(&self) -> String {
-        self.title
-            .chars()
-            .chain(iter::once('\n'))
-            .chain(self.description.chars())
-            .chain(iter::once('\n'))
-            .chain(self.code.chars())
-            .collect()
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Marks the command as always having to be confirmed.
+    pub fn as_dangerous(mut self) -> Self {
+        self.dangerous = true;
+        self
+    }
+
+    /// Marks the code as using the template syntax.
+    pub fn as_template(mut self) -> Self {
+        self.template = true;
+        self
+    }
+
+    /// Converts the entry into the text that's searched when ranking.
+    pub fn to_haystack(&self) -> Haystack {
+        Haystack {
+            title: Text::new(&self.title),
+            description: Text::new(&self.description),
+            code: Text::new(&self.code),
+            tags: self.tags.iter().map(|t| Text::new(t)).collect(),
+        }
     }
 }
 
-impl Widget for Entry {
+/// Renders the details of an entry, highlighting the characters that
+/// matched a search.
+pub struct EntryPreview<'a> {
+    pub entry: &'a Entry,
+    pub positions: &'a Positions,
+}
+
+impl Widget for EntryPreview<'_> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
     where
         Self: Sized,
     {
+        let Self { entry, positions } = self;
         let block = Block::bordered();
         // +2 for borders
-        let code_height = cmp::max(1, self.code.lines().count() as u16) + 2;
+        let code_height = cmp::max(1, entry.code.lines().count() as u16) + 2;
         let layout = vertical![==1, ==1, ==code_height, ==1, *=1].split(block.inner(area));
 
-        let title = self.title.bold();
-        let code_block = Paragraph::new(self.code).block(Block::bordered().title("Command"));
+        let title = highlight(&entry.title, &positions.title).bold();
+        let tags = entry.tags.join(", ").dark_gray();
+        let code_block = Paragraph::new(highlight(&entry.code, &positions.code))
+            .block(Block::bordered().title("Command"));
         block.render(area, buf);
         title.render(layout[0], buf);
+        tags.render(layout[1], buf);
         code_block.render(layout[2], buf);
-        self.description.render(layout[4], buf);
+        highlight(&entry.description, &positions.description).render(layout[4], buf);
     }
 }
 
@@ -84,6 +166,9 @@ impl Entries {
 #[derive(Debug)]
 pub struct Data {
     entries: Entries,
+    /// Kept in sync with the entries, so it isn't built again for every
+    /// search.
+    index: Bm25Index,
     file: File,
 }
 
@@ -92,18 +177,16 @@ impl Data {
         let mut str = String::new();
         file.read_to_string(&mut str).context("corrupted file")?;
         let entries = if str.trim().is_empty() {
-            Data {
-                entries: Entries::new(),
-                file,
-            }
+            Entries::new()
         } else {
-            Data {
-                entries: toml::from_str(&str)?,
-                file,
-            }
+            toml::from_str(&str)?
         };
 
-        Ok(entries)
+        Ok(Data {
+            index: Bm25Index::new(&entries.entries),
+            entries,
+            file,
+        })
     }
 
     fn write_to_file(&mut self) -> Result<()> {
@@ -115,21 +198,47 @@ impl Data {
     }
 
     pub fn add(&mut self, entry: Entry) -> Result<()> {
+        self.index.push(&entry);
         self.entries.entries.push(entry);
         self.write_to_file()
     }
 
+    /// Adds many entries at once, only writing to the file a single time.
+    pub fn add_all(&mut self, entries: impl IntoIterator<Item = Entry>) -> Result<()> {
+        for entry in entries {
+            self.index.push(&entry);
+            self.entries.entries.push(entry);
+        }
+        self.write_to_file()
+    }
+
     pub fn remove(&mut self, index: usize) -> Result<()> {
         self.entries.entries.remove(index);
+        self.index.remove(index);
         self.write_to_file()
     }
 
     pub fn edit(&mut self, index: usize, new: Entry) -> Result<()> {
+        self.index.replace(index, &new);
         self.entries.entries[index] = new;
         self.write_to_file()
     }
 
+    /// Records that an entry was used, forgetting its oldest uses.
+    pub fn record_use(&mut self, index: usize, usage: Usage) -> Result<()> {
+        let entry = &mut self.entries.entries[index];
+        entry.used += 1;
+        entry.history.push(usage);
+        let excess = entry.history.len().saturating_sub(MAX_HISTORY);
+        entry.history.drain(..excess);
+        self.write_to_file()
+    }
+
     pub fn entries(&self) -> &[Entry] {
         &self.entries.entries
     }
+
+    pub fn index(&self) -> &Bm25Index {
+        &self.index
+    }
 }
@@ -0,0 +1,43 @@
+//! Converting [`Entry`]s into other formats.
+
+mod json;
+mod markdown;
+mod navi;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+
+use crate::{
+    db::Entry,
+    template::{self, TemplatedCommand},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A cheat sheet, with a section for each tag.
+    Markdown,
+    /// A list of every entry and their inputs.
+    Json,
+    /// A navi `.cheat` file.
+    Navi,
+}
+
+pub fn export(format: Format, entries: &[Entry]) -> Result<String> {
+    match format {
+        Format::Markdown => Ok(markdown::export(entries)),
+        Format::Json => json::export(entries),
+        Format::Navi => Ok(navi::export(entries)),
+    }
+}
+
+/// Parses the code of an entry, treating it as a literal if it isn't a
+/// template or isn't a valid one.
+fn parse_code(entry: &Entry) -> TemplatedCommand {
+    let literal = || template::parse(&template::escape(&entry.code));
+    let template = if entry.template {
+        template::parse(&entry.code).or_else(|_| literal())
+    } else {
+        literal()
+    };
+    template.expect("escaped code should have no inputs")
+}
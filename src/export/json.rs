@@ -0,0 +1,40 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::db::Entry;
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a Entry,
+    /// The code with the default value of each input.
+    command: String,
+    inputs: Vec<Input<'a>>,
+}
+
+#[derive(Serialize)]
+struct Input<'a> {
+    description: &'a str,
+    default: String,
+}
+
+pub fn export(entries: &[Entry]) -> Result<String> {
+    let templates = entries.iter().map(super::parse_code).collect::<Vec<_>>();
+    let entries = entries
+        .iter()
+        .zip(&templates)
+        .map(|(entry, template)| JsonEntry {
+            entry,
+            command: template.display(),
+            inputs: template
+                .inputs()
+                .map(|(default, description)| Input {
+                    description,
+                    default,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
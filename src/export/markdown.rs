@@ -0,0 +1,138 @@
+use std::fmt::Write as _;
+
+use itertools::Itertools as _;
+
+use crate::db::Entry;
+
+/// Heading for entries without any tags.
+const UNTAGGED: &str = "Other";
+
+pub fn export(entries: &[Entry]) -> String {
+    let tags = entries
+        .iter()
+        .flat_map(|e| &e.tags)
+        .map(String::as_str)
+        .sorted()
+        .dedup()
+        .collect_vec();
+
+    let mut md = String::from("# Cheat sheet\n");
+    for tag in &tags {
+        write_section(
+            &mut md,
+            tag,
+            entries.iter().filter(|e| e.tags.iter().any(|t| t == tag)),
+        );
+    }
+    write_section(
+        &mut md,
+        UNTAGGED,
+        entries.iter().filter(|e| e.tags.is_empty()),
+    );
+    md
+}
+
+fn write_section<'a>(md: &mut String, heading: &str, entries: impl Iterator<Item = &'a Entry>) {
+    let mut entries = entries.peekable();
+    if entries.peek().is_none() {
+        return;
+    }
+
+    _ = write!(md, "\n## {heading}\n");
+    for entry in entries {
+        write_entry(md, entry);
+    }
+}
+
+fn write_entry(md: &mut String, entry: &Entry) {
+    let template = super::parse_code(entry);
+    let command = template.map_inputs(|default, desc| match (default, desc) {
+        (default, "") if !default.is_empty() => default.to_string(),
+        (_, "") => "<input>".to_string(),
+        (_, desc) => format!("<{desc}>"),
+    });
+
+    _ = write!(md, "\n### {}\n\n", entry.title);
+    if !entry.description.is_empty() {
+        _ = write!(md, "{}\n\n", entry.description.trim_end());
+    }
+    _ = writeln!(md, "```sh\n{command}\n```");
+
+    let inputs = template
+        .inputs()
+        .filter(|(_, desc)| !desc.is_empty())
+        .unique()
+        .collect_vec();
+    if !inputs.is_empty() {
+        md.push('\n');
+    }
+    for (default, desc) in inputs {
+        if default.is_empty() {
+            _ = writeln!(md, "- `<{desc}>`");
+        } else {
+            _ = writeln!(md, "- `<{desc}>`: defaults to `{default}`");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export;
+    use crate::db::Entry;
+
+    #[test]
+    fn groups_by_tag() {
+        let entries = [
+            Entry::new("Change branch", "git checkout [main#branch]", "").with_tags(["git"]),
+            Entry::new("List files", "ls [-a] [#dir]", "Shows hidden files."),
+            Entry::new("Build image", "docker build [.#context]", "").with_tags(["docker", "git"]),
+        ]
+        .map(Entry::as_template);
+
+        assert_eq!(
+            export(&entries),
+            "# Cheat sheet
+
+## docker
+
+### Build image
+
+```sh
+docker build <context>
+```
+
+- `<context>`: defaults to `.`
+
+## git
+
+### Change branch
+
+```sh
+git checkout <branch>
+```
+
+- `<branch>`: defaults to `main`
+
+### Build image
+
+```sh
+docker build <context>
+```
+
+- `<context>`: defaults to `.`
+
+## Other
+
+### List files
+
+Shows hidden files.
+
+```sh
+ls -a <dir>
+```
+
+- `<dir>`
+"
+        );
+    }
+}
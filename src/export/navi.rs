@@ -0,0 +1,197 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::{db::Entry, import::navi::UNDESCRIBED_VARIABLE, template};
+
+/// Entries that share the same tags and variables.
+struct Block<'a> {
+    tags: &'a [String],
+    cheats: Vec<String>,
+    /// Variable name to its default value, which is empty for variables
+    /// without a generator.
+    variables: Vec<(String, String)>,
+}
+
+impl<'a> Block<'a> {
+    fn new(tags: &'a [String]) -> Self {
+        Self {
+            tags,
+            cheats: Vec::new(),
+            variables: Vec::new(),
+        }
+    }
+
+    /// Whether a variable has the same name, but a different default value,
+    /// to one already in the block.
+    fn conflicts(&self, variables: &[(String, String)]) -> bool {
+        variables
+            .iter()
+            .any(|(var, default)| self.variables.iter().any(|(v, d)| v == var && d != default))
+    }
+
+    fn write(&self, navi: &mut String) {
+        if self.cheats.is_empty() {
+            return;
+        }
+        _ = writeln!(navi, "% {}", self.tags.join(", "));
+        for cheat in &self.cheats {
+            _ = write!(navi, "\n{cheat}");
+        }
+        let generators = self.variables.iter().filter(|(_, d)| !d.is_empty());
+        if generators.clone().next().is_some() {
+            navi.push('\n');
+        }
+        for (var, default) in generators {
+            _ = writeln!(navi, "$ {var}: echo {}", quote(default));
+        }
+        navi.push('\n');
+    }
+}
+
+pub fn export(entries: &[Entry]) -> String {
+    // group entries by their tags, keeping the order of the first entry
+    let mut groups = Vec::<(&[String], Vec<&Entry>)>::new();
+    for entry in entries {
+        match groups.iter_mut().find(|(tags, _)| *tags == entry.tags) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((&entry.tags, vec![entry])),
+        }
+    }
+
+    let mut navi = String::new();
+    for (tags, group) in groups {
+        let mut block = Block::new(tags);
+        for entry in group {
+            let (cheat, variables) = to_cheat(entry);
+            if block.conflicts(&variables) {
+                block.write(&mut navi);
+                block = Block::new(tags);
+            }
+            block.cheats.push(cheat);
+            for variable in variables {
+                if !block.variables.contains(&variable) {
+                    block.variables.push(variable);
+                }
+            }
+        }
+        block.write(&mut navi);
+    }
+    navi
+}
+
+/// Converts an entry into a navi command, returning the command and
+/// its variables with their default values.
+///
+/// Every input gets its own variable, named after its description, and
+/// inputs that are edited together share one.
+fn to_cheat(entry: &Entry) -> (String, Vec<(String, String)>) {
+    let template = super::parse_code(entry);
+    // input number to its variable name
+    let mut names = HashMap::<usize, String>::new();
+    let mut variables = Vec::new();
+
+    let code = template.map_numbered_inputs(|default, desc, number| {
+        let var = match names.get(&number) {
+            Some(var) => var.clone(),
+            None => {
+                let mut var = variable_name(desc, number);
+                if names.values().any(|v| *v == var) {
+                    var = format!("{var}_{}", number + 1);
+                }
+                names.insert(number, var.clone());
+                var
+            }
+        };
+        if !variables.iter().any(|(v, _)| *v == var) {
+            variables.push((var.clone(), default.to_string()));
+        }
+        format!("<{var}>")
+    });
+
+    let mut cheat = String::new();
+    for line in entry.description.lines() {
+        _ = writeln!(cheat, "{}", format!("; {line}").trim_end());
+    }
+    _ = writeln!(cheat, "# {}", entry.title.replace('\n', " "));
+    _ = writeln!(cheat, "{code}");
+    (cheat, variables)
+}
+
+/// Converts an input description into a valid navi variable name, or
+/// names it after its number if it has no description.
+fn variable_name(desc: &str, number: usize) -> String {
+    if desc.is_empty() {
+        return format!("{}{}", UNDESCRIBED_VARIABLE, number + 1);
+    }
+    let name = desc
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name
+    } else {
+        format!("{}{}{name}", UNDESCRIBED_VARIABLE, number + 1)
+    }
+}
+
+/// Quotes a value to be echoed by a shell, only if needed.
+fn quote(s: &str) -> String {
+    if !s.is_empty() && template::is_shell_safe(s) {
+        s.to_string()
+    } else if !s.contains('\'') {
+        format!("'{s}'")
+    } else {
+        let mut quoted = String::from('"');
+        for c in s.chars() {
+            if matches!(c, '\\' | '"' | '$' | '`') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export;
+    use crate::{db::Entry, import::Format};
+
+    #[test]
+    fn round_trips() {
+        let entries = [
+            Entry::new("Change branch", "git checkout [main#branch]", "").with_tags(["git"]),
+            Entry::new("Diff", "git diff [main#from]..[#to]", "").with_tags(["git"]),
+            Entry::new("Tag", "docker tag [#image#1] [#image#1]:[latest#tag#2]", "")
+                .with_tags(["docker"]),
+            Entry::new("Checkout", "git checkout [dev#branch]", "").with_tags(["git"]),
+            Entry::new("Message", "echo [hello world#msg] \\[x\\]", ""),
+            Entry::new(
+                "Copy",
+                "cp [a.txt] [b.txt]",
+                "Copy a file\n\nkeeping the original",
+            ),
+            Entry::new("Back up", "cp [##1] [##1].bak && ls [#dir#2]", ""),
+        ]
+        .map(Entry::as_template);
+
+        let navi = export(&entries);
+        let imported = Format::Navi.parse(navi.clone().into_bytes()).unwrap();
+        assert_eq!(imported.len(), entries.len(), "{navi}");
+        for entry in &entries {
+            assert!(
+                imported.iter().any(|i| i.title == entry.title
+                    && i.code == entry.code
+                    && i.description == entry.description
+                    && i.tags == entry.tags),
+                "{entry:?} not found in:\n{navi}"
+            );
+        }
+    }
+}
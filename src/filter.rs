@@ -0,0 +1,156 @@
+//! Printing ranked entries without the interactive search, like
+//! `fzf --filter`.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::{
+    context::Environment,
+    db::{Data, Entry},
+    rank::{self, Options},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// The title and score of each entry, followed by its indented code.
+    #[default]
+    Plain,
+    /// A line for each entry with its id, score, title and code, where
+    /// tabs, newlines and backslashes are escaped.
+    Tsv,
+    /// A list of entries with their id and score.
+    Json,
+}
+
+#[derive(Serialize)]
+struct JsonMatch<'a> {
+    id: usize,
+    score: f32,
+    #[serde(flatten)]
+    entry: &'a Entry,
+}
+
+/// Ranks the entries of `data` with a query, keeping at most `limit` entries that score
+/// at least `min_score`.
+pub fn filter(
+    format: Format,
+    options: &Options,
+    environment: &Environment,
+    query: &str,
+    data: &Data,
+    limit: Option<usize>,
+    min_score: Option<f32>,
+) -> Result<String> {
+    let entries = data.entries();
+    let matches = rank::rank(options, environment, query, entries, data.index())
+        .into_iter()
+        .take_while(|m| min_score.is_none_or(|min| m.score >= min))
+        .take(limit.unwrap_or(usize::MAX))
+        // ids shown to the user start from 1
+        .map(|m| (m.index + 1, m.score, &entries[m.index]));
+
+    let mut out = String::new();
+    match format {
+        Format::Plain => {
+            for (_, score, entry) in matches {
+                _ = writeln!(out, "{} ({score:.4})", entry.title);
+                for line in entry.code.lines() {
+                    _ = writeln!(out, "    {line}");
+                }
+            }
+        }
+        Format::Tsv => {
+            for (id, score, entry) in matches {
+                _ = writeln!(
+                    out,
+                    "{id}\t{score:.4}\t{}\t{}",
+                    escape_tsv(&entry.title),
+                    escape_tsv(&entry.code)
+                );
+            }
+        }
+        Format::Json => {
+            let matches = matches
+                .map(|(id, score, entry)| JsonMatch { id, score, entry })
+                .collect::<Vec<_>>();
+            out = serde_json::to_string_pretty(&matches)?;
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+fn escape_tsv(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter, Format};
+    use crate::{context::Environment, db::Entry, rank::Options, utils::fixtures::empty_data};
+
+    #[test]
+    fn filters() {
+        let mut data = empty_data("filter");
+        data.add_all([
+            Entry::new("list files", "ls -la", ""),
+            Entry::new("git status", "git status", "show changed files"),
+            Entry::new("loop", "for f in *; do\n\techo $f\ndone", "loop over files"),
+        ])
+        .unwrap();
+
+        let out = filter(
+            Format::Tsv,
+            &Options::default(),
+            &Environment::default(),
+            "loop",
+            &data,
+            Some(1),
+            None,
+        )
+        .unwrap();
+        assert_eq!(out.lines().count(), 1);
+        assert!(out.starts_with("3\t"));
+        assert!(out.ends_with("\tloop\tfor f in *; do\\n\\techo $f\\ndone\n"));
+
+        let out = filter(
+            Format::Plain,
+            &Options::default(),
+            &Environment::default(),
+            "git status",
+            &data,
+            None,
+            Some(1.0),
+        )
+        .unwrap();
+        assert!(out.starts_with("git status ("));
+        assert!(out.contains("\n    git status\n"));
+        assert!(!out.contains("ls -la"));
+
+        let out = filter(
+            Format::Json,
+            &Options::default(),
+            &Environment::default(),
+            "",
+            &data,
+            None,
+            None,
+        )
+        .unwrap();
+        let json = serde_json::from_str::<serde_json::Value>(&out).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,87 @@
+//! Queries that were searched before, saved one per line.
+
+use std::{
+    fs::File,
+    io::{Read, Seek, Write},
+};
+
+use color_eyre::eyre::{Context, Result};
+
+/// Number of queries kept in the history.
+const MAX_QUERIES: usize = 1000;
+
+#[derive(Debug)]
+pub struct QueryHistory {
+    /// Oldest first, without duplicates.
+    queries: Vec<String>,
+    file: File,
+}
+
+impl QueryHistory {
+    pub fn load_from(mut file: File) -> Result<Self> {
+        let mut str = String::new();
+        file.read_to_string(&mut str)
+            .context("corrupted query history")?;
+        Ok(Self {
+            queries: str
+                .lines()
+                .filter(|q| !q.trim().is_empty())
+                .map(str::to_owned)
+                .collect(),
+            file,
+        })
+    }
+
+    /// Adds a query as the most recent one, forgetting the oldest queries.
+    pub fn add(&mut self, query: &str) -> Result<()> {
+        let query = query.trim();
+        if query.is_empty() || query.contains('\n') {
+            return Ok(());
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.push(query.to_owned());
+        let excess = self.queries.len().saturating_sub(MAX_QUERIES);
+        self.queries.drain(..excess);
+        self.write_to_file()
+    }
+
+    /// The queries, most recent first.
+    pub fn recent(&self) -> impl Iterator<Item = &str> + '_ {
+        self.queries.iter().rev().map(String::as_str)
+    }
+
+    fn write_to_file(&mut self) -> Result<()> {
+        let mut doc = self.queries.join("\n");
+        doc.push('\n');
+        self.file.set_len(0)?;
+        self.file.rewind()?;
+        self.file.write_all(doc.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::utils::fixtures::{open_history, temp_path};
+
+    #[test]
+    fn adds() {
+        let path = temp_path("history");
+        fs::write(&path, "docker\n\ngit\n").unwrap();
+
+        let mut history = open_history(&path);
+        history.add(" docker ").unwrap();
+        history.add("").unwrap();
+        history.add("tar").unwrap();
+        assert_eq!(
+            history.recent().collect::<Vec<_>>(),
+            ["tar", "docker", "git"]
+        );
+        assert_eq!(
+            open_history(&path).recent().collect::<Vec<_>>(),
+            ["tar", "docker", "git"]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Converting other snippet formats into [`Entry`]s.
+
+mod history;
+pub(crate) mod navi;
+mod pet;
+mod tldr;
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{Context, Result};
+
+use crate::db::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// navi `.cheat` files. Directories are searched recursively.
+    Navi,
+    /// tldr-pages markdown files. Directories are searched recursively,
+    /// and only the English `pages` of a tldr-pages checkout are imported.
+    Tldr,
+    /// pet `snippet.toml` files.
+    Pet,
+    /// zsh history file. Defaults to `~/.zsh_history`.
+    ZshHistory,
+    /// bash history file. Defaults to `~/.bash_history`.
+    BashHistory,
+}
+
+impl Format {
+    /// File extension that files of this format are expected to have when
+    /// importing from a directory.
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Navi => "cheat",
+            Format::Tldr => "md",
+            Format::Pet => "toml",
+            Format::ZshHistory | Format::BashHistory => "",
+        }
+    }
+
+    /// Path to import from when none is given.
+    pub fn default_path(self) -> Option<PathBuf> {
+        match self {
+            Format::ZshHistory => Some(dirs::home_dir()?.join(".zsh_history")),
+            Format::BashHistory => Some(dirs::home_dir()?.join(".bash_history")),
+            Format::Navi | Format::Tldr | Format::Pet => None,
+        }
+    }
+
+    /// Whether the entries are imported from shell history, and should
+    /// be chosen from instead of all being added.
+    pub fn is_history(self) -> bool {
+        matches!(self, Format::ZshHistory | Format::BashHistory)
+    }
+
+    /// Parses the entries of a file, whose code is converted into the
+    /// template syntax.
+    pub fn parse(self, src: Vec<u8>) -> Result<Vec<Entry>> {
+        let entries = if self == Format::ZshHistory {
+            // zsh history isn't necessarily valid utf-8
+            history::parse_zsh(&src)
+        } else {
+            let src = String::from_utf8(src).context("file is not valid utf-8")?;
+            match self {
+                Format::Navi => navi::parse(&src),
+                Format::Tldr => tldr::parse(&src),
+                Format::Pet => pet::parse(&src)?,
+                Format::BashHistory => history::parse_bash(&src),
+                Format::ZshHistory => unreachable!(),
+            }
+        };
+        Ok(entries.into_iter().map(Entry::as_template).collect())
+    }
+}
+
+/// Reads all entries from a file, or every file in a directory with
+/// the extension expected by the format.
+pub fn import(format: Format, path: &Path) -> Result<Vec<Entry>> {
+    // a checkout also has translations in `pages.<language>`, which would
+    // be imported again with translated placeholders
+    let pages = path.join("pages");
+    if format == Format::Tldr && pages.is_dir() {
+        return import(format, &pages);
+    }
+
+    if path.is_dir() {
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(path)
+            .with_context(|| format!("unable to read directory {}", path.display()))?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        // keep imports in a consistent order
+        dir.sort();
+
+        for path in dir {
+            if path.is_dir()
+                || path
+                    .extension()
+                    .is_some_and(|ext| ext == format.extension())
+            {
+                entries.extend(import(format, &path)?);
+            }
+        }
+        Ok(entries)
+    } else {
+        let src = fs::read(path).with_context(|| format!("unable to read {}", path.display()))?;
+        format
+            .parse(src)
+            .with_context(|| format!("unable to import {}", path.display()))
+    }
+}
+
+/// Removes entries that have the same code as an existing entry, or as
+/// another entry being imported.
+pub fn without_duplicates(entries: Vec<Entry>, existing: &[Entry]) -> Vec<Entry> {
+    let mut seen: HashSet<String> = existing.iter().map(|e| e.code.clone()).collect();
+    entries
+        .into_iter()
+        .filter(|e| seen.insert(e.code.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{import, Format};
+
+    #[test]
+    fn imports_english_tldr_pages() {
+        let root = env::temp_dir().join(format!("how-test-{}-tldr", process::id()));
+        let page = "# ls\n\n> List files.\n\n- List files:\n\n`ls`\n";
+        for dir in ["pages/common", "pages.fr/common"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("ls.md"), page).unwrap();
+        }
+        fs::write(root.join("README.md"), "# tldr-pages\n").unwrap();
+
+        let entries = import(Format::Tldr, &root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].code, "ls");
+    }
+}
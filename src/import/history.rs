@@ -0,0 +1,152 @@
+//! Importing commands from shell history files.
+//!
+//! Every command becomes an entry with the command as the title, most
+//! recent first. As history files contain a lot of noise, these should be
+//! picked through before being added.
+
+use crate::{db::Entry, template};
+
+/// Character zsh uses to mark that the next byte has been "metafied".
+const ZSH_META: u8 = 0x83;
+
+/// Reads a zsh history file.
+///
+/// Commands may be in the extended history format, like
+/// `: 1700000000:0;git status`, and multi-line commands have each line
+/// ending with a backslash.
+pub fn parse_zsh(src: &[u8]) -> Vec<Entry> {
+    let src = unmetafy(src);
+    let mut commands = Vec::new();
+    let mut current = String::new();
+
+    for line in src.lines() {
+        let line = if current.is_empty() {
+            strip_extended_prefix(line)
+        } else {
+            line
+        };
+
+        match line.strip_suffix('\\') {
+            Some(line) => {
+                current.push_str(line);
+                current.push('\n');
+            }
+            None => {
+                current.push_str(line);
+                commands.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        commands.push(current);
+    }
+
+    to_entries(commands)
+}
+
+/// Reads a bash history file.
+///
+/// If `HISTTIMEFORMAT` is set, each command is preceded by a `#<timestamp>`
+/// line, which is used to group multi-line commands. Otherwise, every line
+/// is a separate command.
+pub fn parse_bash(src: &str) -> Vec<Entry> {
+    let is_timestamp = |line: &str| {
+        line.strip_prefix('#')
+            .is_some_and(|t| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit()))
+    };
+
+    let commands = if src.lines().any(is_timestamp) {
+        let mut commands = Vec::new();
+        let mut current = Vec::new();
+        for line in src.lines() {
+            if is_timestamp(line) {
+                commands.push(current.join("\n"));
+                current.clear();
+            } else {
+                current.push(line);
+            }
+        }
+        commands.push(current.join("\n"));
+        commands
+    } else {
+        src.lines().map(String::from).collect()
+    };
+
+    to_entries(commands)
+}
+
+fn to_entries(commands: Vec<String>) -> Vec<Entry> {
+    commands
+        .into_iter()
+        .rev()
+        .filter(|c| !c.trim().is_empty())
+        .map(|c| {
+            let title = c.lines().next().unwrap_or_default().trim();
+            Entry::new(title, template::escape(&c), "")
+        })
+        .collect()
+}
+
+/// Removes the `: <start>:<elapsed>;` prefix of extended history lines.
+fn strip_extended_prefix(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix(": ") else {
+        return line;
+    };
+    match rest.split_once(';') {
+        Some((time, command))
+            if time
+                .split(':')
+                .all(|t| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            command
+        }
+        _ => line,
+    }
+}
+
+/// Undoes zsh's encoding of special bytes in the history file.
+fn unmetafy(src: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(src.len());
+    let mut iter = src.iter();
+    while let Some(&b) = iter.next() {
+        if b == ZSH_META {
+            bytes.extend(iter.next().map(|b| b ^ 32));
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bash, parse_zsh};
+
+    #[test]
+    fn parses_zsh() {
+        let mut src =
+            b": 1700000000:0;git status\n: 1700000005:3;for f in *; do\\\n  echo [$f]\\\ndone\nls "
+                .to_vec();
+        // "à" is 0xC3 0xA0, where 0xA0 is metafied
+        src.extend([0xC3, 0x83, 0xA0 ^ 32, b'\n']);
+
+        let entries = parse_zsh(&src);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].code, "ls à");
+        assert_eq!(entries[1].title, "for f in *; do");
+        assert_eq!(entries[1].code, "for f in *; do\n  echo \\[$f\\]\ndone");
+        assert_eq!(entries[2].code, "git status");
+    }
+
+    #[test]
+    fn parses_bash() {
+        let entries = parse_bash("ls\ncd ..\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].code, "cd ..");
+
+        let entries = parse_bash("#1700000000\nls\n#1700000001\nfor f in *; do\n  echo $f\ndone\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].code, "for f in *; do\n  echo $f\ndone");
+        assert_eq!(entries[1].code, "ls");
+    }
+}
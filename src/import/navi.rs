@@ -0,0 +1,315 @@
+//! Importing from [navi](https://github.com/denisidoro/navi) cheatsheets.
+//!
+//! A cheatsheet looks like:
+//! ```text
+//! % git, code
+//!
+//! # Change branch
+//! git checkout <branch>
+//!
+//! $ branch: git branch | awk '{print $NF}'
+//! ```
+//! - `%` lines set the tags for every following command, until the next `%`.
+//! - `#` lines are the description of the next command, used as the title.
+//! - `;` comment lines right before a command are its description.
+//! - `<var>` placeholders are converted into template inputs, described by
+//!   the variable name unless it's one of the `<input1>`, `<input2>`, ...
+//!   names that exporting gives inputs without a description.
+//! - `$ var: command` generators are scoped to the current `%` block. If the
+//!   generator only echoes a single value, it is used as the default of the
+//!   input. Any other generator is kept in the entry's description.
+//! - `@` extensions are ignored.
+
+use std::collections::HashMap;
+
+use itertools::Itertools as _;
+
+use crate::{db::Entry, template};
+
+/// Start of the names of variables for inputs without a description, which
+/// are followed by the input's number.
+pub const UNDESCRIBED_VARIABLE: &str = "input";
+
+/// A command and its title, before variables are resolved.
+struct Cheat {
+    title: String,
+    description: String,
+    code: String,
+}
+
+#[derive(Default)]
+struct Parser {
+    entries: Vec<Entry>,
+    tags: Vec<String>,
+    /// Variable name to its generator command.
+    generators: HashMap<String, String>,
+    cheats: Vec<Cheat>,
+    title: Vec<String>,
+    /// Comments since the last command.
+    comments: Vec<String>,
+    code: Vec<String>,
+}
+
+impl Parser {
+    fn read_line(&mut self, line: &str) {
+        let trimmed = line.trim();
+        if let Some(tags) = trimmed.strip_prefix('%') {
+            self.finish_block();
+            self.tags = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect();
+        } else if let Some(title) = trimmed.strip_prefix('#') {
+            self.finish_cheat();
+            self.title.push(title.trim().to_string());
+        } else if let Some(generator) = trimmed.strip_prefix('$') {
+            self.finish_cheat();
+            if let Some((var, command)) = generator.split_once(':') {
+                // options for the suggestions are written after a `---`
+                let command = command.split(" --- ").next().unwrap_or_default();
+                self.generators
+                    .insert(var.trim().to_string(), command.trim().to_string());
+            }
+        } else if let Some(comment) = trimmed.strip_prefix(';') {
+            self.finish_cheat();
+            let comment = comment.strip_prefix(' ').unwrap_or(comment);
+            self.comments.push(comment.to_string());
+        } else if trimmed.is_empty() || trimmed.starts_with('@') {
+            // comments separated from a command aren't about it
+            if self.title.is_empty() && self.code.is_empty() {
+                self.comments.clear();
+            }
+            self.finish_cheat();
+        } else {
+            self.code.push(line.trim_end().to_string());
+        }
+    }
+
+    fn finish_cheat(&mut self) {
+        if self.code.is_empty() {
+            return;
+        }
+        let code = self.code.drain(..).join("\n");
+        let title = if self.title.is_empty() {
+            code.clone()
+        } else {
+            self.title.drain(..).join(" ")
+        };
+        let description = self.comments.drain(..).join("\n");
+        self.cheats.push(Cheat {
+            title,
+            description,
+            code,
+        });
+    }
+
+    /// Converts all cheats in the current `%` block into entries, now that
+    /// all the generators in the block are known.
+    fn finish_block(&mut self) {
+        self.finish_cheat();
+        self.title.clear();
+        self.comments.clear();
+        for cheat in self.cheats.drain(..) {
+            let (code, notes) = to_template(&cheat.code, &self.generators);
+            let description = Some(cheat.description)
+                .filter(|d| !d.is_empty())
+                .into_iter()
+                .chain(notes)
+                .join("\n");
+            self.entries
+                .push(Entry::new(cheat.title, code, description).with_tags(self.tags.clone()));
+        }
+        self.generators.clear();
+    }
+}
+
+pub fn parse(src: &str) -> Vec<Entry> {
+    let mut parser = Parser::default();
+    for line in src.lines() {
+        parser.read_line(line);
+    }
+    parser.finish_block();
+    parser.entries
+}
+
+/// Splits a command into literal text and `<var>` placeholders.
+fn split_placeholders(code: &str) -> Vec<Result<&str, &str>> {
+    let mut sections = Vec::new();
+    let mut rest = code;
+    while let Some(start) = rest.find('<') {
+        let name = rest[start + 1..]
+            .split_once('>')
+            .map(|(name, _)| name)
+            .filter(|name| is_variable_name(name));
+        match name {
+            Some(name) => {
+                sections.push(Ok(&rest[..start]));
+                sections.push(Err(name));
+                rest = &rest[start + name.len() + 2..];
+            }
+            None => {
+                sections.push(Ok(&rest[..=start]));
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    sections.push(Ok(rest));
+    sections
+}
+
+fn is_variable_name(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Converts a navi command into the template syntax, returning the
+/// template and notes about generators that could not be converted.
+fn to_template(code: &str, generators: &HashMap<String, String>) -> (String, Vec<String>) {
+    let sections = split_placeholders(code);
+    let variables = sections
+        .iter()
+        .filter_map(|s| s.err())
+        .unique()
+        .collect_vec();
+    // the same variable used multiple times should be edited together,
+    // which needs explicit indexes.
+    let needs_index = variables.len() != sections.iter().filter(|s| s.is_err()).count();
+
+    let mut notes = Vec::new();
+    for var in &variables {
+        match generators.get(*var) {
+            Some(generator) if echoed_value(generator).is_none() => {
+                notes.push(format!("`{var}` suggestions: `{generator}`"))
+            }
+            _ => {}
+        }
+    }
+
+    let mut template = String::new();
+    for section in sections {
+        match section {
+            Ok(literal) => template.push_str(&template::escape(literal)),
+            Err(var) => {
+                let default = generators
+                    .get(var)
+                    .and_then(|g| echoed_value(g))
+                    .unwrap_or_default();
+                template.push('[');
+                template.push_str(&template::escape(default));
+                if !is_undescribed(var) {
+                    template.push('#');
+                    template.push_str(&template::escape(var));
+                }
+                if needs_index {
+                    if is_undescribed(var) {
+                        template.push('#');
+                    }
+                    let index = variables.iter().position(|v| *v == var).unwrap() + 1;
+                    template.push_str(&format!("#{index}"));
+                }
+                template.push(']');
+            }
+        }
+    }
+
+    (template, notes)
+}
+
+/// Whether a variable is named like the inputs without a description.
+fn is_undescribed(var: &str) -> bool {
+    var.strip_prefix(UNDESCRIBED_VARIABLE)
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Gets the value of a generator that only echoes a single value,
+/// like `echo main` or `echo "my value"`.
+fn echoed_value(generator: &str) -> Option<&str> {
+    let value = generator.strip_prefix("echo ")?.trim();
+    let unquoted = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')));
+
+    match unquoted {
+        Some(inner) if !inner.contains(['"', '\'', '$', '`', '\\']) => Some(inner),
+        Some(_) => None,
+        None if !value.contains(|c: char| c.is_whitespace() || "|;&<>()$`'\"\\".contains(c)) => {
+            Some(value)
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::template;
+
+    const CHEAT: &str = r#"
+% git, code
+
+# Change branch
+git checkout <branch>
+
+# Diff two refs
+git diff <from>..<to>
+
+$ branch: git branch | awk '{print $NF}' --- --column 1
+$ from: echo main
+
+% docker
+
+; a comment
+# Rename a tag, keeping
+# the old one
+docker tag <image> <image>:<tag>
+
+$ tag: echo "latest"
+
+echo "<not a var>" && [[ -f a ]] 2> /dev/null
+"#;
+
+    #[test]
+    fn parses_cheats() {
+        let entries = parse(CHEAT);
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].title, "Change branch");
+        assert_eq!(entries[0].code, "git checkout [#branch]");
+        assert_eq!(
+            entries[0].description,
+            "`branch` suggestions: `git branch | awk '{print $NF}'`"
+        );
+        assert_eq!(entries[0].tags, ["git", "code"]);
+
+        assert_eq!(entries[1].code, "git diff [main#from]..[#to]");
+        assert_eq!(entries[1].description, "");
+
+        assert_eq!(entries[2].title, "Rename a tag, keeping the old one");
+        assert_eq!(
+            entries[2].code,
+            "docker tag [#image#1] [#image#1]:[latest#tag#2]"
+        );
+        assert_eq!(entries[2].tags, ["docker"]);
+        assert_eq!(entries[2].description, "a comment");
+
+        let literal = r#"echo "<not a var>" && [[ -f a ]] 2> /dev/null"#;
+        assert_eq!(entries[3].title, literal);
+        assert_eq!(
+            template::parse(&entries[3].code).unwrap().display(),
+            literal
+        );
+    }
+
+    #[test]
+    fn templates_are_valid() {
+        for entry in parse(CHEAT) {
+            template::parse(&entry.code).unwrap();
+        }
+    }
+}
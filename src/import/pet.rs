@@ -0,0 +1,130 @@
+//! Importing from [pet](https://github.com/knqyf263/pet) `snippet.toml` files.
+//!
+//! A snippet file looks like:
+//! ```toml
+//! [[snippets]]
+//!   description = "Checkout a branch"
+//!   command = "git checkout <branch=main>"
+//!   tag = ["git"]
+//!   output = ""
+//! ```
+//! - `<param>` and `<param=default>` parameters are converted into
+//!   template inputs.
+//! - The output is appended to the description, if there is one.
+
+use color_eyre::eyre::{Context, Result};
+use serde::Deserialize;
+
+use crate::{db::Entry, template};
+
+#[derive(Debug, Deserialize)]
+struct Snippets {
+    #[serde(default)]
+    snippets: Vec<Snippet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Snippet {
+    description: String,
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+    #[serde(default)]
+    output: String,
+}
+
+pub fn parse(src: &str) -> Result<Vec<Entry>> {
+    let snippets: Snippets = toml::from_str(src).context("invalid pet snippet file")?;
+    Ok(snippets
+        .snippets
+        .into_iter()
+        .map(|s| {
+            let description = if s.output.trim().is_empty() {
+                String::new()
+            } else {
+                format!("Output:\n{}", s.output.trim_end())
+            };
+            let title = if s.description.is_empty() {
+                s.command.clone()
+            } else {
+                s.description
+            };
+            Entry::new(title, to_template(&s.command), description).with_tags(s.tag)
+        })
+        .collect())
+}
+
+/// Converts a pet command into the template syntax.
+fn to_template(code: &str) -> String {
+    let mut template = String::new();
+    let mut rest = code;
+    while let Some(start) = rest.find('<') {
+        let param = rest[start + 1..]
+            .split_once('>')
+            .map(|(param, _)| param)
+            .filter(|param| is_parameter(param));
+        match param {
+            Some(param) => {
+                let (name, default) = param.split_once('=').unwrap_or((param, ""));
+                template.push_str(&template::escape(&rest[..start]));
+                template.push('[');
+                template.push_str(&template::escape(default));
+                template.push('#');
+                template.push_str(&template::escape(name));
+                template.push(']');
+                rest = &rest[start + param.len() + 2..];
+            }
+            None => {
+                template.push_str(&template::escape(&rest[..=start]));
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    template.push_str(&template::escape(rest));
+    template
+}
+
+/// Whether the text between `<` and `>` is a parameter, rather than
+/// something like a redirection.
+fn is_parameter(s: &str) -> bool {
+    let name = s.split_once('=').map_or(s, |(name, _)| name);
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    const SNIPPETS: &str = r#"
+[[snippets]]
+  description = "Checkout a branch"
+  command = "git checkout <branch=main> && git log <ref> -1 > /dev/null"
+  tag = ["git"]
+  output = ""
+
+[[snippets]]
+  description = ""
+  command = "echo [hi]"
+  output = "[hi]\n"
+"#;
+
+    #[test]
+    fn parses_snippets() {
+        let entries = parse(SNIPPETS).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].title, "Checkout a branch");
+        assert_eq!(
+            entries[0].code,
+            "git checkout [main#branch] && git log [#ref] -1 > /dev/null"
+        );
+        assert_eq!(entries[0].tags, ["git"]);
+
+        assert_eq!(entries[1].title, "echo [hi]");
+        assert_eq!(entries[1].code, r"echo \[hi\]");
+        assert_eq!(entries[1].description, "Output:\n[hi]");
+    }
+}
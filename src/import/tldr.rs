@@ -0,0 +1,146 @@
+//! Importing from [tldr-pages](https://github.com/tldr-pages/tldr).
+//!
+//! A page looks like:
+//! ````markdown
+//! # tar
+//!
+//! > Archiving utility.
+//! > More information: <https://www.gnu.org/software/tar>.
+//!
+//! - Create an archive from files:
+//!
+//! `tar cf {{path/to/target.tar}} {{path/to/file1 path/to/file2 ...}}`
+//! ````
+//! - Every example becomes an entry, tagged with the page name.
+//! - `{{placeholder}}`s are converted into template inputs, with the
+//!   placeholder text as the description.
+//! - Option placeholders like `{{[-c|--create]}}` are replaced by their
+//!   first alternative.
+//! - The first line of the page's summary is used as the description.
+//! - Markdown files that don't start like a page, with a heading and a
+//!   summary, have no entries.
+
+use crate::{db::Entry, template};
+
+pub fn parse(src: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    if !is_page(src) {
+        return entries;
+    }
+    let mut name = None;
+    let mut summary = None;
+    let mut title = None;
+
+    for line in src.lines().map(str::trim) {
+        if let Some(page) = line.strip_prefix("# ") {
+            name = Some(page.trim().to_string());
+        } else if let Some(desc) = line.strip_prefix('>') {
+            let desc = desc.trim();
+            if summary.is_none() && !desc.starts_with("More information") {
+                summary = Some(desc.to_string());
+            }
+        } else if let Some(example) = line.strip_prefix("- ") {
+            let example = example.trim();
+            title = Some(example.strip_suffix(':').unwrap_or(example).to_string());
+        } else if let Some(code) = line.strip_prefix('`').and_then(|l| l.strip_suffix('`')) {
+            let Some(title) = title.take() else {
+                continue;
+            };
+            entries.push(
+                Entry::new(
+                    title,
+                    to_template(code),
+                    summary.clone().unwrap_or_default(),
+                )
+                .with_tags(name.clone()),
+            );
+        }
+    }
+
+    entries
+}
+
+/// Whether a markdown file is a page, unlike the readme and contributing
+/// guides of the repository.
+fn is_page(src: &str) -> bool {
+    let mut lines = src.lines().map(str::trim).filter(|l| !l.is_empty());
+    lines.next().is_some_and(|l| l.starts_with("# "))
+        && lines.next().is_some_and(|l| l.starts_with('>'))
+}
+
+/// Converts a tldr command into the template syntax.
+fn to_template(code: &str) -> String {
+    let mut template = String::new();
+    let mut rest = code;
+    while let Some((literal, after)) = rest.split_once("{{") {
+        let Some((placeholder, after)) = after.split_once("}}") else {
+            break;
+        };
+        template.push_str(&template::escape(literal));
+        match option_placeholder(placeholder) {
+            Some(option) => template.push_str(&template::escape(option)),
+            None => {
+                template.push_str("[#");
+                template.push_str(&template::escape(placeholder));
+                template.push(']');
+            }
+        }
+        rest = after;
+    }
+    template.push_str(&template::escape(rest));
+    template
+}
+
+/// Gets the first option of a placeholder like `[-c|--create]`.
+fn option_placeholder(placeholder: &str) -> Option<&str> {
+    placeholder
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split('|')
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    const PAGE: &str = r#"# tar
+
+> Archiving utility.
+> Often combined with a compression method, such as `gzip` or `bzip2`.
+> More information: <https://www.gnu.org/software/tar>.
+
+- [c]reate an archive and write it to a [f]ile:
+
+`tar {{[-c|--create]}} {{[-f|--file]}} {{path/to/target.tar}} {{path/to/file1 path/to/file2 ...}}`
+
+- List the contents of a tar file [v]erbosely:
+
+`tar tvf {{path/to/source.tar}}`
+"#;
+
+    #[test]
+    fn parses_page() {
+        let entries = parse(PAGE);
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(
+            entries[0].title,
+            "[c]reate an archive and write it to a [f]ile"
+        );
+        assert_eq!(
+            entries[0].code,
+            "tar -c -f [#path/to/target.tar] [#path/to/file1 path/to/file2 ...]"
+        );
+        assert_eq!(entries[0].description, "Archiving utility.");
+        assert_eq!(entries[0].tags, ["tar"]);
+
+        assert_eq!(entries[1].code, "tar tvf [#path/to/source.tar]");
+    }
+
+    #[test]
+    fn skips_other_markdown() {
+        let readme = "# Contributing\n\n- Open a pull request:\n\n`git push`\n";
+        assert!(parse(readme).is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+#![feature(let_chains)]
+
+pub mod config;
+pub mod context;
+pub mod danger;
+pub mod db;
+pub mod export;
+pub mod filter;
+pub mod history;
+pub mod import;
+pub mod rank;
+pub mod redirect;
+pub mod shell;
+pub mod template;
+pub mod ui;
+pub mod utils;
+pub mod widgets;
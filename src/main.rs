@@ -1,16 +1,12 @@
-#![feature(let_chains)]
-
-mod db;
-mod rank;
-mod template;
-mod ui;
-mod utils;
-mod widgets;
-
 use std::{
-    fs,
+    collections::HashSet,
+    env, fs,
     io::{self, stderr, BufWriter, Write},
-    process::{self, Command},
+    path::PathBuf,
+    process::Command,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
 
 use clap::Parser;
@@ -18,28 +14,168 @@ use color_eyre::{
     eyre::{Context, ContextCompat},
     Result,
 };
+use config::Config;
 use crossterm::{
+    cursor::{self, MoveTo},
     event::{self, Event, KeyEventKind},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
-use db::Data;
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use db::{Data, Entry};
+use how::{
+    config, context::Environment, db, export, filter, history::QueryHistory, import, redirect,
+    shell, template, ui, widgets,
+};
+use ratatui::{prelude::CrosstermBackend, widgets::Widget, Terminal, TerminalOptions, Viewport};
+use shell::{Shell, WidgetMode};
+use template::Quoting;
+use tui_textarea::Input;
 use ui::App;
+use widgets::Picker;
 
 #[derive(Debug, Parser)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+    /// Without a subcommand, `how [query]...` is the same as
+    /// `how search [query]...`.
+    #[command(flatten)]
+    search: SearchArgs,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Subcommand {
+    /// Echoes the integration script for a shell.
+    ///
+    /// This should be added to your shell's config:
+    ///
+    /// - zsh: `source <(how init zsh)`
+    ///
+    /// - bash: `source <(how init bash)`
+    ///
+    /// - fish: `how init fish | source`
+    ///
+    /// - nushell: `how init nu | save --force ~/.cache/how.nu`, then
+    ///   `source ~/.cache/how.nu`, as nushell cannot source generated scripts.
+    ///
+    /// - PowerShell: `how init pwsh | Out-String | Invoke-Expression`
+    ///
+    /// By default, Ctrl-_ searches for a command to insert, and
+    /// Ctrl-X Ctrl-_ saves the previous command as a new entry
+    /// (zsh, bash and fish only).
+    Init {
+        shell: Shell,
+        /// Key to search with, in the shell's own syntax.
+        ///
+        /// For example, `^G` in zsh, `\C-g` in bash, `\cg` in fish,
+        /// `control+char_g` in nushell or `Ctrl+g` in PowerShell.
+        #[arg(long)]
+        key: Option<String>,
+        /// What the key does with the selected command. Only zsh supports
+        /// modes other than insert.
+        #[arg(long, value_enum, default_value_t)]
+        mode: WidgetMode,
+        /// Key to save the previous command with, in the shell's own syntax.
+        #[arg(long)]
+        save_last_key: Option<String>,
+        /// Don't include the widget to save the previous command.
+        #[arg(long, conflicts_with = "save_last_key")]
+        no_save_last: bool,
+    },
+    /// Searches for a command, printing it to stdout.
+    Search(SearchArgs),
+    /// Opens the editor to add a new entry.
+    Add {
+        /// Initial code of the entry.
+        #[arg(long)]
+        code: Option<String>,
+        /// Fills the entry's inputs, like `[default#description]`, when
+        /// it's selected, instead of inserting its code literally.
+        #[arg(long)]
+        template: bool,
+        /// Always confirms the command before executing it, even if it
+        /// doesn't look destructive.
+        #[arg(long)]
+        dangerous: bool,
+    },
+    /// Lists every entry with its id.
+    List {
+        /// Only list entries with this tag.
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Shows the details of an entry.
+    Show {
+        /// Id of the entry, as shown by `how list`.
+        id: usize,
+    },
+    /// Opens the editor for an existing entry.
+    Edit {
+        /// Id of the entry, as shown by `how list`.
+        id: usize,
+    },
+    /// Removes an entry.
+    Rm {
+        /// Id of the entry, as shown by `how list`.
+        id: usize,
+    },
+    /// Adds entries from another snippet format to the database.
+    Import {
+        /// Format of the files to import.
+        #[arg(long, value_enum)]
+        format: import::Format,
+        /// File or directory to import from.
+        ///
+        /// Can be omitted for shell history, which will use the shell's
+        /// default history file.
+        path: Option<PathBuf>,
+    },
+    /// Writes every entry in the database in another format.
+    Export {
+        /// Format to write entries in.
+        #[arg(long, value_enum)]
+        format: export::Format,
+        /// File to write to, instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct SearchArgs {
     /// Immediately executes the command instead of printing to stdout.
-    #[arg(long)]
-    execute: bool,
-    /// Only echoes the zsh integration script.
     ///
-    /// This should be added to your `.zshrc` like so:
+    /// Entries marked as dangerous, or commands that look destructive,
+    /// have to be confirmed first.
+    #[arg(long, conflicts_with = "filter")]
+    execute: bool,
+    /// Prints the command to stderr before executing it.
+    #[arg(long, requires = "execute")]
+    echo: bool,
+    /// Prints the ranked entries for the query instead of opening the
+    /// search.
+    #[arg(long)]
+    filter: bool,
+    /// Format to print ranked entries in, with `--filter`.
+    #[arg(long, value_enum, default_value_t, requires = "filter")]
+    output: filter::Format,
+    /// Maximum number of entries to print, with `--filter`.
+    #[arg(long, requires = "filter")]
+    limit: Option<usize>,
+    /// Minimum score of entries to print, with `--filter`.
+    #[arg(long, requires = "filter")]
+    min_score: Option<f32>,
+    /// Shows the search below the cursor with this height, instead of
+    /// taking up the whole screen.
     ///
-    /// `source <(how --zsh)`
-    #[arg(long, exclusive = true)]
-    zsh: bool,
+    /// Either a number of lines, or a percentage of the terminal's height
+    /// like `40%`.
+    #[arg(long, env = "HOW_HEIGHT")]
+    height: Option<Height>,
+    /// How to quote values filled into a command's inputs.
+    #[arg(long, value_enum, default_value_t)]
+    quoting: Quoting,
     /// An initial query to insert. Can be quoted or unquoted,
     /// in which case, each argument will be separated by a space.
     ///
@@ -49,81 +185,422 @@ struct Args {
     ///
     /// To avoid accidentally setting flags, insert text after a `--`.
     ///
-    /// For example: `how --execute -- initial -h query`.
+    /// For example: `how search --execute -- initial -h query`.
     /// This sets the `--execute` flag, and has an initial query of
     /// "initial -h query".
     query: Vec<String>,
 }
 
+/// Height of an inline search.
+#[derive(Debug, Clone, Copy)]
+enum Height {
+    Lines(u16),
+    Percent(u16),
+}
+
+impl Height {
+    /// Number of lines to use in a terminal, leaving enough room to show
+    /// the search and a few entries.
+    fn lines(self, terminal_height: u16) -> u16 {
+        let lines = match self {
+            Height::Lines(lines) => lines,
+            Height::Percent(percent) => {
+                (u32::from(terminal_height) * u32::from(percent) / 100) as u16
+            }
+        };
+        lines.clamp(MIN_HEIGHT.min(terminal_height), terminal_height)
+    }
+}
+
+const MIN_HEIGHT: u16 = 6;
+
+impl FromStr for Height {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, percent) = match s.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+        let number = number
+            .parse::<u16>()
+            .map_err(|_| format!("expected a number of lines or a percentage, got `{s}`"))?;
+        match (percent, number) {
+            (true, 1..=100) => Ok(Height::Percent(number)),
+            (true, _) => Err("percentage must be between 1% and 100%".to_string()),
+            (false, 0) => Err("height must be at least 1 line".to_string()),
+            (false, _) => Ok(Height::Lines(number)),
+        }
+    }
+}
+
 fn main() -> Result<()> {
     run().inspect_err(|_| _ = restore())
 }
 
 fn run() -> Result<()> {
     let args = Args::parse();
-    if args.zsh {
-        println!(
-            "### start: how integration.zsh ###\n{}\n### end: how integration.zsh ###\n",
-            include_str!("../shell/integration.zsh")
-        );
+    let Some(command) = args.command else {
+        return search(args.search);
+    };
+    match command {
+        Subcommand::Init {
+            shell,
+            key,
+            mode,
+            save_last_key,
+            no_save_last,
+        } => {
+            let key = key.as_deref().unwrap_or(shell.default_key());
+            let widget = shell
+                .widget_name(mode)
+                .with_context(|| format!("{mode:?} mode is not supported for {shell:?}"))?;
+            let save_last_key = save_last_key
+                .as_deref()
+                .or(shell.default_save_last_key())
+                .filter(|_| !no_save_last);
+            print!("{}", shell.integration(key, widget, save_last_key));
+            Ok(())
+        }
+        Subcommand::Search(args) => search(args),
+        Subcommand::Add {
+            code,
+            template,
+            dangerous,
+        } => add_entry(code.unwrap_or_default(), template, dangerous),
+        Subcommand::List { tag } => list_entries(tag),
+        Subcommand::Show { id } => show_entry(id),
+        Subcommand::Edit { id } => edit_entry(id),
+        Subcommand::Rm { id } => remove_entry(id),
+        Subcommand::Import { format, path } => import_entries(format, path),
+        Subcommand::Export { format, output } => export_entries(format, output),
+    }
+}
+
+fn search(args: SearchArgs) -> Result<()> {
+    let data = open_data()?;
+    let config = Config::load()?;
+    let environment = Environment::detect();
+    if args.filter {
+        let query = args.query.join(" ");
+        let out = filter::filter(
+            args.output,
+            &config.rank_options(),
+            &environment,
+            &query,
+            &data,
+            args.limit,
+            args.min_score,
+        )?;
+        print!("{out}");
         return Ok(());
     }
 
-    let dir = dirs::data_dir().context("unable to find data directory")?;
+    let mut app = App::new(data, args.query.join(" "))
+        .set_quoting(args.quoting)
+        .set_rank_options(config.rank_options())
+        .set_environment(environment)
+        .set_history(open_history()?)
+        .set_confirm_dangerous(args.execute);
+    let viewport = match args.height {
+        Some(height) => Viewport::Inline(height.lines(crossterm::terminal::size()?.1)),
+        None => Viewport::Fullscreen,
+    };
+    let output = run_tui_in(
+        &mut app,
+        viewport,
+        |app, input| {
+            Ok(match app.read(input)? {
+                ui::AppControl::Become(s) => Some(Some(s)),
+                ui::AppControl::Exit => Some(None),
+                ui::AppControl::Continue => None,
+            })
+        },
+        App::update,
+    )?;
 
-    let file = fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(dir.join("how-db.toml"))
-        .context("unable to open how-db.toml")?;
+    if let Some(s) = output {
+        app.record_query()?;
+        if args.execute {
+            if args.echo {
+                eprintln!("$ {s}");
+            }
+            execute(&s)?;
+        } else {
+            println!("{s}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a command with the user's shell, exiting with its exit code.
+///
+/// On unix, this process is replaced with the shell, so signals and exit
+/// statuses are handled as if the command was run directly.
+fn execute(command: &str) -> Result<()> {
+    let mut shell = if cfg!(target_os = "windows") {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new(env::var_os("SHELL").unwrap_or_else(|| "sh".into()));
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt as _;
+        Err(shell.exec()).context("unable to run shell")
+    }
+    #[cfg(not(unix))]
+    {
+        let status = shell.status().context("unable to run shell")?;
+        std::process::exit(status.code().unwrap_or(1))
+    }
+}
+
+/// Converts an id shown to the user into an index into the entries.
+fn entry_index(data: &Data, id: usize) -> Result<usize> {
+    id.checked_sub(1)
+        .filter(|&i| i < data.entries().len())
+        .with_context(|| format!("no entry with id {id}, see `how list`"))
+}
+
+fn list_entries(tag: Option<String>) -> Result<()> {
+    let data = open_data()?;
+    let mut stdout = io::stdout().lock();
+    for (i, entry) in data.entries().iter().enumerate() {
+        if tag.as_ref().is_some_and(|tag| !entry.tags.contains(tag)) {
+            continue;
+        }
+        if entry.tags.is_empty() {
+            writeln!(stdout, "{:>4}  {}", i + 1, entry.title)?;
+        } else {
+            writeln!(
+                stdout,
+                "{:>4}  {} [{}]",
+                i + 1,
+                entry.title,
+                entry.tags.join(", ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn show_entry(id: usize) -> Result<()> {
+    let data = open_data()?;
+    let entry = &data.entries()[entry_index(&data, id)?];
+    println!("{}", entry.title);
+    if !entry.tags.is_empty() {
+        println!("tags: {}", entry.tags.join(", "));
+    }
+    println!("\n{}", entry.code);
+    if !entry.description.is_empty() {
+        println!("\n{}", entry.description);
+    }
+    Ok(())
+}
+
+fn edit_entry(id: usize) -> Result<()> {
+    let data = open_data()?;
+    let index = entry_index(&data, id)?;
+    run_editor(App::new_editing(data, index))
+}
+
+fn remove_entry(id: usize) -> Result<()> {
+    let mut data = open_data()?;
+    let index = entry_index(&data, id)?;
+    let title = data.entries()[index].title.clone();
+    data.remove(index)?;
+    eprintln!("removed {title}");
+    Ok(())
+}
+
+fn import_entries(format: import::Format, path: Option<PathBuf>) -> Result<()> {
+    let path = path
+        .or_else(|| format.default_path())
+        .context("no path given to import from")?;
+    let mut data = open_data()?;
+    let entries = import::import(format, &path)?;
+    let total = entries.len();
+    let mut entries = import::without_duplicates(entries, data.entries());
+    let duplicates = total - entries.len();
+    if format.is_history() {
+        entries = pick_entries(entries)?;
+    }
+    let count = entries.len();
+    data.add_all(entries)?;
+    eprintln!("imported {count} entries, skipped {duplicates} duplicates");
+    Ok(())
+}
+
+fn export_entries(format: export::Format, output: Option<PathBuf>) -> Result<()> {
+    let exported = export::export(format, open_data()?.entries())?;
+    match output {
+        Some(path) => fs::write(&path, exported)
+            .with_context(|| format!("unable to write to {}", path.display()))?,
+        None => print!("{exported}"),
+    }
+    Ok(())
+}
+
+fn add_entry(code: String, template: bool, dangerous: bool) -> Result<()> {
+    let mut entry = Entry::new("", code.trim_end(), "");
+    if template {
+        entry = entry.as_template();
+    }
+    if dangerous {
+        entry = entry.as_dangerous();
+    }
+    run_editor(App::new_adding(open_data()?, entry))
+}
+
+/// Runs an app that only shows the entry editor.
+fn run_editor(mut app: App) -> Result<()> {
+    run_tui(&mut app, |app, input| {
+        Ok(match app.read(input)? {
+            ui::AppControl::Continue => None,
+            _ => Some(()),
+        })
+    })
+}
 
+/// How long to wait for input before calling `tick` again.
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs a full screen app until `read` returns an output.
+fn run_tui<S, T>(state: &mut S, read: impl FnMut(&mut S, Input) -> Result<Option<T>>) -> Result<T>
+where
+    for<'a> &'a S: Widget,
+{
+    run_tui_in(state, Viewport::Fullscreen, read, |_| false)
+}
+
+/// Runs an app in a viewport until `read` returns an output.
+///
+/// `tick` is called regularly between inputs, and the app is drawn again
+/// if it returns true.
+///
+/// Only full screen and inline viewports are supported. An inline
+/// viewport is drawn below the line with the cursor, like a shell prompt,
+/// and is cleared afterwards, leaving the cursor where it started.
+fn run_tui_in<S, T>(
+    state: &mut S,
+    viewport: Viewport,
+    mut read: impl FnMut(&mut S, Input) -> Result<Option<T>>,
+    mut tick: impl FnMut(&mut S) -> bool,
+) -> Result<T>
+where
+    for<'a> &'a S: Widget,
+{
     // https://ratatui.rs/faq/#should-i-use-stdout-or-stderr
     // same as `ratatui::restore()` but with stderr instead.
     set_panic_hook();
     enable_raw_mode()?;
-    stderr().execute(EnterAlternateScreen)?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(BufWriter::new(stderr())))?;
+    let inline = matches!(viewport, Viewport::Inline(_));
+    if inline {
+        redirect::stdout_to_stderr()?;
+    } else {
+        stderr().execute(EnterAlternateScreen)?;
+        ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+    }
+    let cursor = if inline {
+        let cursor = cursor::position()?;
+        // the viewport starts at the cursor, which would draw over the prompt
+        stderr().write_all(b"\r\n")?;
+        Some(cursor)
+    } else {
+        None
+    };
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(BufWriter::new(stderr())),
+        TerminalOptions { viewport },
+    )?;
+    // the lines above scroll up if there isn't enough room below them
+    let viewport_top = terminal.get_frame().area().y;
     terminal.clear()?;
 
-    let data = Data::load_from(file)?;
-    let mut app = App::new(data, args.query.join(" "));
+    let mut redraw = true;
     let output = loop {
-        terminal.draw(|f| f.render_widget(&app, f.area()))?;
+        if tick(state) || redraw {
+            terminal.draw(|f| f.render_widget(&*state, f.area()))?;
+        }
+        redraw = event::poll(TICK_INTERVAL)?;
+        if !redraw {
+            continue;
+        }
         if let Event::Key(input) = event::read()? {
             if input.kind == KeyEventKind::Release {
                 continue;
             }
-            match app.read(input.into())? {
-                ui::AppControl::Become(s) => break Some(s),
-                ui::AppControl::Exit => break None,
-                ui::AppControl::Continue => {}
+            if let Some(output) = read(state, input.into())? {
+                break output;
             }
         }
     };
 
+    if inline {
+        terminal.clear()?;
+    }
+    drop(terminal);
     restore()?;
+    if let Some((column, _)) = cursor {
+        stderr().execute(MoveTo(column, viewport_top.saturating_sub(1)))?;
+    }
+    Ok(output)
+}
 
-    if let Some(s) = output {
-        if args.execute {
-            let output = if cfg!(target_os = "windows") {
-                Command::new("cmd").arg("/C").arg(&s).output()?
-            } else {
-                Command::new("sh").arg("-c").arg(&s).output()?
-            };
-
-            // show output of that command
-            _ = io::stdout().write_all(&output.stdout);
-            _ = io::stderr().write_all(&output.stderr);
-            if let Some(code) = output.status.code() {
-                process::exit(code);
-            }
-        } else {
-            println!("{s}");
-        }
+/// Lets the user choose which entries to keep.
+fn pick_entries(entries: Vec<Entry>) -> Result<Vec<Entry>> {
+    let mut picker = Picker::new(
+        entries
+            .iter()
+            .map(|e| template::unescape(&e.code))
+            .collect(),
+        "Choose commands to add",
+    );
+    let confirmed = run_tui(&mut picker, |picker, input| Ok(picker.read(input)))?;
+    if !confirmed {
+        return Ok(Vec::new());
     }
 
-    Ok(())
+    let selected = picker.selected().collect::<HashSet<_>>();
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, e)| selected.contains(&i).then_some(e))
+        .collect())
+}
+
+fn open_data() -> Result<Data> {
+    let dir = dirs::data_dir().context("unable to find data directory")?;
+
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(dir.join("how-db.toml"))
+        .context("unable to open how-db.toml")?;
+
+    Data::load_from(file)
+}
+
+fn open_history() -> Result<QueryHistory> {
+    let dir = dirs::data_dir().context("unable to find data directory")?;
+
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(dir.join("how-history"))
+        .context("unable to open how-history")?;
+
+    QueryHistory::load_from(file)
 }
 
 fn set_panic_hook() {
@@ -134,8 +611,60 @@ fn set_panic_hook() {
     }));
 }
 
+/// Whether the terminal has been switched to the alternate screen.
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
 fn restore() -> io::Result<()> {
     disable_raw_mode()?;
-    stderr().execute(LeaveAlternateScreen)?;
-    Ok(())
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        stderr().execute(LeaveAlternateScreen)?;
+    }
+    redirect::restore_stdout()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{Args, SearchArgs, Subcommand};
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(["how"].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn searches_without_subcommand() {
+        let args = parse(&["--execute", "docker", "logs"]);
+        assert!(args.command.is_none());
+        assert!(args.search.execute);
+        assert_eq!(args.search.query, ["docker", "logs"]);
+
+        assert!(parse(&[]).command.is_none());
+        assert!(matches!(
+            parse(&["search", "--filter", "list"]).command,
+            Some(Subcommand::Search(SearchArgs { filter: true, query, .. })) if query == ["list"]
+        ));
+        assert!(matches!(
+            parse(&["list"]).command,
+            Some(Subcommand::List { .. })
+        ));
+        // after an option of the search, subcommand names are part of the query
+        assert_eq!(parse(&["--execute", "list"]).search.query, ["list"]);
+    }
+
+    #[test]
+    fn add_flags() {
+        assert!(matches!(
+            parse(&["add", "--template", "--code", "cd [dir]"]).command,
+            Some(Subcommand::Add { code: Some(code), template: true, dangerous: false }) if code == "cd [dir]"
+        ));
+        assert!(matches!(
+            parse(&["add", "--dangerous"]).command,
+            Some(Subcommand::Add {
+                code: None,
+                template: false,
+                dangerous: true
+            })
+        ));
+    }
 }
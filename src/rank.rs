@@ -1,31 +1,1004 @@
+//! Ranking entries against a search query.
+
+mod bm25;
+mod haystack;
+mod query;
+mod subsequence;
+mod tolerance;
+mod trigram;
+mod worker;
+
+use std::iter;
+
+use clap::ValueEnum;
 use itertools::Itertools as _;
-use rust_fuzzy_search::fuzzy_compare;
+use serde::Deserialize;
+
+use self::query::{Field, Query, Term};
+pub use self::{
+    bm25::Bm25Index,
+    haystack::{Haystack, Text},
+    tolerance::{Synonyms, Vocabulary},
+    worker::Worker,
+};
+use crate::{
+    context::Environment,
+    db::{self, Entry},
+};
+
+/// Scores terms of a query against the fields of entries.
+pub trait Ranker {
+    /// Scores how well a term matches the text of a field, with the indexes
+    /// of the characters that matched, or `None` if it doesn't match at all.
+    fn score(&self, term: &Text, text: &Text) -> Option<(f32, Vec<usize>)>;
+
+    /// Whether adding characters to a term can only make it match fewer
+    /// fields, so a longer query only has to rank the results of a shorter
+    /// one.
+    fn narrows(&self) -> bool {
+        false
+    }
+}
+
+/// The rankers that can be chosen in the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// Similarity of the trigrams in the query and each field.
+    #[default]
+    Trigram,
+    /// Matches the query's characters in order, like fzf and skim, with
+    /// bonuses for word boundaries and consecutive characters.
+    Subsequence,
+}
+
+impl Algorithm {
+    pub fn ranker(self) -> &'static dyn Ranker {
+        match self {
+            Algorithm::Trigram => &trigram::Trigram,
+            Algorithm::Subsequence => &subsequence::Subsequence,
+        }
+    }
+}
+
+/// Which entries are searched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Every entry.
+    #[default]
+    Global,
+    /// Entries that were used in the current directory or repository.
+    Directory,
+}
+
+impl Scope {
+    pub fn toggle(self) -> Self {
+        match self {
+            Scope::Global => Scope::Directory,
+            Scope::Directory => Scope::Global,
+        }
+    }
+}
+
+/// How entries are ranked, set in the config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    pub algorithm: Algorithm,
+    pub weights: Weights,
+    pub boosts: Boosts,
+    pub scope: Scope,
+    pub synonyms: Synonyms,
+    /// Entries scoring less than this are left out, unless the query is
+    /// empty.
+    pub min_score: f32,
+}
+
+/// How much a term matching each field adds to an entry's score.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub title: f32,
+    pub description: f32,
+    pub code: f32,
+    /// For the words of the query found by [`Bm25Index`], relative to the
+    /// best score of the other fields so it adds as much with every
+    /// algorithm.
+    pub bm25: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            title: 2.0,
+            description: 1.0,
+            code: 1.5,
+            bm25: 0.5,
+        }
+    }
+}
+
+/// How much an entry's score is multiplied by when it's relevant where
+/// `how` was launched.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Boosts {
+    /// For entries with an active [context](crate::context).
+    pub context: f32,
+    /// For entries used in the current directory or repository, which is
+    /// scaled down the longer ago they were used, down to nothing after a
+    /// week.
+    pub directory: f32,
+}
+
+impl Default for Boosts {
+    fn default() -> Self {
+        Self {
+            context: 1.5,
+            directory: 1.0,
+        }
+    }
+}
+
+/// An entry that was ranked against a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// Index of the entry.
+    pub index: usize,
+    pub score: f32,
+    pub positions: Positions,
+    /// Whether one of the entry's contexts is active.
+    pub in_context: bool,
+}
+
+/// Indexes of the characters in each field of an entry that matched the
+/// query, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Positions {
+    pub title: Vec<usize>,
+    pub description: Vec<usize>,
+    pub code: Vec<usize>,
+}
+
+impl Positions {
+    fn field_mut(&mut self, field: Field) -> Option<&mut Vec<usize>> {
+        match field {
+            Field::Title => Some(&mut self.title),
+            Field::Description => Some(&mut self.description),
+            Field::Code => Some(&mut self.code),
+            Field::Tag => None,
+        }
+    }
+}
+
+/// Ranks the entries that match a query, best first.
+///
+/// See [`Searcher::rank`] for how entries are ranked.
+pub fn rank(
+    options: &Options,
+    environment: &Environment,
+    query: &str,
+    entries: &[Entry],
+    index: &Bm25Index,
+) -> Vec<Match> {
+    Searcher::new(options.clone(), environment, entries, index)
+        .rank(query, || false)
+        .expect("ranking is never cancelled")
+}
+
+/// Ranks entries against queries as they are typed, keeping the searched
+/// text of every entry.
+pub struct Searcher {
+    options: Options,
+    haystacks: Vec<Haystack>,
+    index: Bm25Index,
+    vocabulary: Vocabulary,
+    relevance: Vec<Relevance>,
+    /// The last query that was ranked, and the indexes of the entries that
+    /// matched it.
+    previous: Option<(Query, Vec<usize>)>,
+}
+
+/// Number of entries to rank between checking for cancellation.
+const CANCEL_CHECK_INTERVAL: usize = 256;
+
+impl Searcher {
+    pub fn new(
+        options: Options,
+        environment: &Environment,
+        entries: &[Entry],
+        index: &Bm25Index,
+    ) -> Self {
+        let now = db::unix_time();
+        let haystacks = entries.iter().map(Entry::to_haystack).collect_vec();
+        Self {
+            options,
+            vocabulary: Vocabulary::new(&haystacks),
+            haystacks,
+            index: index.clone(),
+            relevance: entries
+                .iter()
+                .map(|e| Relevance::new(e, environment, now))
+                .collect(),
+            previous: None,
+        }
+    }
+
+    /// Ranks the entries that match a query, best first, or `None` if
+    /// `is_cancelled` returns true before it finishes.
+    ///
+    /// The query is parsed into terms as described in [`query`], and an
+    /// entry's score is the sum of each term's score plus its BM25 score
+    /// for the words of the query, boosted by how relevant the entry is
+    /// where `how` was launched. Entries with the same score are ranked by
+    /// that boost, and otherwise keep their order.
+    ///
+    /// If the query has any terms that are scored, entries that score zero
+    /// are left out, as they don't match the query at all. Entries where
+    /// some terms don't match are kept if they have any of the words, as
+    /// questions like "how do I undo the last commit" rarely match every
+    /// word.
+    pub fn rank(&mut self, query: &str, is_cancelled: impl Fn() -> bool) -> Option<Vec<Match>> {
+        let ranker = self.options.algorithm.ranker();
+        let query = Query::parse(query);
+        let synonyms = query
+            .terms
+            .iter()
+            .map(|t| {
+                let synonyms = self.options.synonyms.of(&t.text.lower_string);
+                synonyms.iter().map(|s| Text::new(s)).collect_vec()
+            })
+            .collect_vec();
+        let corrections = query
+            .terms
+            .iter()
+            .map(|t| {
+                if t.is_exact() {
+                    return Vec::new();
+                }
+                let corrections = self.vocabulary.corrections(&t.text.lower);
+                corrections
+                    .iter()
+                    .map(|w| Text::new(&w.iter().collect::<String>()))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let is_scored = query
+            .terms
+            .iter()
+            .any(|t| !t.negated && t.field != Some(Field::Tag));
+
+        let words = query
+            .terms
+            .iter()
+            .filter(|t| !t.is_exact() && t.field.is_none())
+            .flat_map(|t| bm25::words(&t.text.lower_string))
+            .collect_vec();
+        let bm25 = if self.options.weights.bm25 > 0.0 {
+            self.index.scores(&words)
+        } else {
+            vec![0.0; self.haystacks.len()]
+        };
+
+        let candidates = match &self.previous {
+            Some((previous, indexes)) if self.can_narrow(previous, &query) => {
+                // entries with any of the words match without every term, so
+                // they can't be narrowed
+                let mut candidates = indexes.clone();
+                candidates.extend((0..bm25.len()).filter(|&i| bm25[i] > 0.0));
+                // and so do the words a term could be a misspelling of, which
+                // the shorter term might not have been
+                candidates.extend(
+                    corrections
+                        .iter()
+                        .flatten()
+                        .flat_map(|c| self.vocabulary.entries(&c.lower)),
+                );
+                candidates.sort_unstable();
+                candidates.dedup();
+                candidates
+            }
+            _ => (0..self.haystacks.len()).collect(),
+        };
+        let mut scored = Vec::new();
+        for (i, chunk) in candidates.chunks(CANCEL_CHECK_INTERVAL).enumerate() {
+            if i > 0 && is_cancelled() {
+                return None;
+            }
+            scored.extend(chunk.iter().filter_map(|&index| {
+                if self.options.scope == Scope::Directory
+                    && self.relevance[index].used_nearby.is_none()
+                {
+                    return None;
+                }
+                let haystack = &self.haystacks[index];
+                let partial = bm25[index] > 0.0;
+                let (score, positions) = score_entry(
+                    ranker,
+                    &self.options.weights,
+                    &query,
+                    &synonyms,
+                    &corrections,
+                    haystack,
+                    partial,
+                )?;
+                Some((index, score, positions))
+            }));
+        }
+
+        // BM25 scores aren't comparable with the ranker's, so they are scaled
+        // for the best one to add the ranker's best score times the weight
+        let best = scored
+            .iter()
+            .map(|(_, score, _)| *score)
+            .fold(0.0, f32::max);
+        let best_bm25 = bm25.iter().copied().fold(0.0, f32::max);
+        let bm25_scale = if best_bm25 > 0.0 {
+            self.options.weights.bm25 * if best > 0.0 { best } else { 1.0 } / best_bm25
+        } else {
+            0.0
+        };
+        let mut matches = scored
+            .into_iter()
+            .filter_map(|(index, score, positions)| {
+                let relevance = self.relevance[index];
+                let score =
+                    (score + bm25[index] * bm25_scale) * relevance.boost(&self.options.boosts);
+                if is_scored && (score <= 0.0 || score < self.options.min_score) {
+                    return None;
+                }
+                Some(Match {
+                    index,
+                    score,
+                    positions,
+                    in_context: relevance.in_context,
+                })
+            })
+            .collect_vec();
+
+        let mut indexes = matches.iter().map(|m| m.index).collect_vec();
+        indexes.sort_unstable();
+        self.previous = Some((query, indexes));
+
+        let boost = |m: &Match| self.relevance[m.index].boost(&self.options.boosts);
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(boost(b).total_cmp(&boost(a)))
+        });
+        Some(matches)
+    }
+
+    /// Whether every entry that matches `query` also matched `previous`.
+    ///
+    /// This is only the case when the query adds terms or characters to
+    /// the end of a term, except negated terms which would then exclude
+    /// less, and terms that then have new synonyms. A minimum score could
+    /// also let entries in that scored too low before.
+    fn can_narrow(&self, previous: &Query, query: &Query) -> bool {
+        if !self.options.algorithm.ranker().narrows() || self.options.min_score > 0.0 {
+            return false;
+        }
+        let Some((last, rest)) = previous.terms.split_last() else {
+            return true;
+        };
+        let Some(extended) = query.terms.get(rest.len()) else {
+            return false;
+        };
+        query.terms.starts_with(rest)
+            && !last.negated
+            && !self.adds_synonyms(last, extended)
+            && extended.text.chars.starts_with(&last.text.chars)
+            && Term {
+                text: last.text.clone(),
+                ..extended.clone()
+            } == *last
+    }
+
+    /// Whether a term has synonyms that the term it extends didn't have,
+    /// which can match entries the shorter term didn't.
+    fn adds_synonyms(&self, last: &Term, extended: &Term) -> bool {
+        let synonyms = &self.options.synonyms;
+        let before = synonyms.of(&last.text.lower_string);
+        !extended.is_exact()
+            && synonyms
+                .of(&extended.text.lower_string)
+                .iter()
+                .any(|s| !before.contains(s))
+    }
+}
+
+/// How relevant an entry is where `how` was launched, regardless of the
+/// query.
+#[derive(Debug, Clone, Copy)]
+struct Relevance {
+    in_context: bool,
+    /// How recently the entry was used in the current directory or
+    /// repository, from 0 to 1, or `None` if it never was.
+    used_nearby: Option<f32>,
+}
+
+impl Relevance {
+    fn new(entry: &Entry, environment: &Environment, now: u64) -> Self {
+        Self {
+            in_context: entry.contexts.iter().any(|c| environment.is_active(c)),
+            used_nearby: entry
+                .history
+                .iter()
+                .filter(|usage| environment.is_nearby(&usage.dir))
+                .map(|usage| recency(now.saturating_sub(usage.time)))
+                .max_by(f32::total_cmp),
+        }
+    }
+
+    /// What the entry's score is multiplied by.
+    fn boost(&self, boosts: &Boosts) -> f32 {
+        let mut boost = 1.0;
+        if self.in_context {
+            boost *= boosts.context;
+        }
+        if let Some(recency) = self.used_nearby {
+            boost *= 1.0 + boosts.directory * recency;
+        }
+        boost
+    }
+}
 
-use crate::db::Entry;
+/// How much a use that was `age` seconds ago counts, like zoxide's aging.
+fn recency(age: u64) -> f32 {
+    const HOUR: u64 = 60 * 60;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    if age < HOUR {
+        1.0
+    } else if age < DAY {
+        0.5
+    } else if age < WEEK {
+        0.25
+    } else {
+        0.0
+    }
+}
+
+/// Scores every term of a query against an entry, or `None` if any term
+/// doesn't match, unless the entry can match `partial`ly, in which case
+/// only filtering terms leave it out.
+///
+/// `synonyms` are the synonyms of each term, and `corrections` the words of
+/// every entry that it could be a misspelling of.
+fn score_entry(
+    ranker: &dyn Ranker,
+    weights: &Weights,
+    query: &Query,
+    synonyms: &[Vec<Text>],
+    corrections: &[Vec<Text>],
+    haystack: &Haystack,
+    partial: bool,
+) -> Option<(f32, Positions)> {
+    let fields = [
+        (Field::Title, &haystack.title, weights.title),
+        (
+            Field::Description,
+            &haystack.description,
+            weights.description,
+        ),
+        (Field::Code, &haystack.code, weights.code),
+    ];
+    let mut score = 0.0;
+    let mut positions = Positions::default();
+
+    for ((term, synonyms), corrections) in query.terms.iter().zip(synonyms).zip(corrections) {
+        if term.field == Some(Field::Tag) {
+            if haystack.tags.iter().any(|tag| term.find(tag).is_some()) == term.negated {
+                return None;
+            }
+            continue;
+        }
+        let fields = fields
+            .iter()
+            .filter(|(field, ..)| term.field.is_none_or(|f| f == *field));
 
-pub fn rank(query: &str, entries: &[Entry]) -> Vec<(usize, f32)> {
-    let query = query.to_lowercase();
-    let mut matches = entries
-        .iter()
-        .enumerate()
-        .map(|(i, entry)| {
-            // varying weightings for each
+        if term.negated {
+            if fields.clone().any(|(_, text, _)| term.find(text).is_some()) {
+                return None;
+            }
+            continue;
+        }
+
+        let mut matched = false;
+        for &(field, text, weight) in fields {
             // must be zero on empty, otherwise no query matches with the field
             // a lot and makes entries with empty fields rank higher.
-            let title_cmp = fuzzy_compare(&query, &entry.title.to_lowercase());
-            let desc_cmp = if entry.description.is_empty() {
-                0.0
-            } else {
-                fuzzy_compare(&query, &entry.description.to_lowercase())
+            if text.is_empty() {
+                continue;
+            }
+            let Some((field_score, field_positions)) =
+                score_term(ranker, term, synonyms, corrections, text)
+            else {
+                continue;
             };
-            let ans_cmp = if entry.description.is_empty() {
-                0.0
-            } else {
-                fuzzy_compare(&query, &entry.code.to_lowercase())
+            matched = true;
+            score += field_score * weight;
+            if let Some(p) = positions.field_mut(field) {
+                p.extend(field_positions);
+            }
+        }
+        if !matched && !partial {
+            return None;
+        }
+    }
+
+    for p in [
+        &mut positions.title,
+        &mut positions.description,
+        &mut positions.code,
+    ] {
+        p.sort_unstable();
+        p.dedup();
+    }
+    Some((score, positions))
+}
+
+/// How much of a term's score is kept when it's matched with a synonym or
+/// a typo, so the words that were typed rank first.
+const SYNONYM_FACTOR: f32 = 0.9;
+const TYPO_FACTOR: f32 = 0.8;
+
+/// Scores a term against a field, or one of its synonyms or the words of
+/// the field it could be a misspelling of if they score better.
+///
+/// Exact terms are only matched as they are.
+fn score_term(
+    ranker: &dyn Ranker,
+    term: &Term,
+    synonyms: &[Text],
+    corrections: &[Text],
+    text: &Text,
+) -> Option<(f32, Vec<usize>)> {
+    if !term.is_exact() {
+        let corrections = corrections
+            .iter()
+            .filter(|c| tolerance::has_word(text, &c.lower));
+        let alternatives = synonyms
+            .iter()
+            .map(|s| (s, SYNONYM_FACTOR))
+            .chain(corrections.map(|c| (c, TYPO_FACTOR)))
+            .filter_map(|(alternative, factor)| {
+                let (score, positions) = ranker.score(alternative, text)?;
+                Some((score * factor, positions))
+            });
+        return iter::once(ranker.score(&term.text, text))
+            .flatten()
+            .chain(alternatives)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+    }
+    let positions = term.find(text)?;
+    let score = ranker
+        .score(&term.text, text)
+        .map_or(0.0, |(score, _)| score);
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde::Deserialize;
+
+    use super::{rank, Algorithm, Bm25Index, Options, Scope, Searcher, Weights};
+    use crate::{
+        context::{Context, Environment},
+        db::{self, Entry, Usage},
+    };
+
+    #[derive(Deserialize)]
+    struct Fixture {
+        entries: Vec<Entry>,
+        cases: Vec<Case>,
+    }
+
+    /// A query and the title of the entry that should be ranked first.
+    #[derive(Deserialize)]
+    struct Case {
+        query: String,
+        best: String,
+    }
+
+    /// Queries where a ranker puts the expected entry first.
+    fn relevant_queries(algorithm: Algorithm) -> Vec<String> {
+        let fixture = toml::from_str::<Fixture>(include_str!("rank/relevance.toml")).unwrap();
+        fixture
+            .cases
+            .into_iter()
+            .filter(|case| {
+                let options = Options {
+                    algorithm,
+                    ..Options::default()
+                };
+                let matches = rank(
+                    &options,
+                    &Environment::default(),
+                    &case.query,
+                    &fixture.entries,
+                    &Bm25Index::new(&fixture.entries),
+                );
+                // ties are kept in order, so the best has to be strictly first
+                match &matches[..] {
+                    [] => false,
+                    [first, rest @ ..] => {
+                        fixture.entries[first.index].title == case.best
+                            && rest.first().is_none_or(|second| second.score < first.score)
+                    }
+                }
+            })
+            .map(|case| case.query)
+            .collect()
+    }
+
+    #[test]
+    fn relevance() {
+        let cases = toml::from_str::<Fixture>(include_str!("rank/relevance.toml"))
+            .unwrap()
+            .cases
+            .len();
+        let trigram = relevant_queries(Algorithm::Trigram);
+        let subsequence = relevant_queries(Algorithm::Subsequence);
+
+        assert_eq!(
+            subsequence.len(),
+            cases,
+            "only {subsequence:?} were relevant"
+        );
+        // abbreviations are the main weakness of trigrams
+        assert!(trigram.len() < subsequence.len());
+        assert!(!trigram.iter().any(|q| q == "gco"));
+    }
+
+    #[test]
+    fn query_terms() {
+        let entries = [
+            Entry::new("remove build", "rm -rf build", "clean up"),
+            Entry::new("list files", "ls -la", "show hidden files").with_tags(["fs"]),
+            Entry::new("sudo list", "sudo ls /root", "list files as root").with_tags(["fs"]),
+        ];
+        let options = Options {
+            algorithm: Algorithm::Subsequence,
+            ..Options::default()
+        };
+        assert_eq!(titles(&options, "ls !code:^sudo", &entries), ["list files"]);
+        assert_eq!(titles(&options, "tag:fs !sudo", &entries), ["list files"]);
+        assert_eq!(
+            titles(&options, "title:list", &entries),
+            ["list files", "sudo list"]
+        );
+        assert_eq!(titles(&options, "\"up\"", &entries), ["remove build"]);
+        assert_eq!(titles(&options, "\"files as\"", &entries), ["sudo list"]);
+        assert_eq!(titles(&options, "build$", &entries), ["remove build"]);
+        assert!(titles(&options, "code:clean", &entries).is_empty());
+    }
+
+    fn titles<'a>(options: &Options, query: &str, entries: &'a [Entry]) -> Vec<&'a str> {
+        rank(
+            options,
+            &Environment::default(),
+            query,
+            entries,
+            &Bm25Index::new(entries),
+        )
+        .into_iter()
+        .map(|m| entries[m.index].title.as_str())
+        .collect()
+    }
+
+    #[test]
+    fn matches_code_without_description() {
+        let entries = [
+            Entry::new("start services", "docker compose up", ""),
+            Entry::new("list files", "ls -la", ""),
+        ];
+        for algorithm in [Algorithm::Trigram, Algorithm::Subsequence] {
+            let options = Options {
+                algorithm,
+                ..Options::default()
+            };
+            assert_eq!(
+                titles(&options, "docker compose", &entries),
+                ["start services"],
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn min_score() {
+        let entries = [
+            Entry::new("restart", "systemctl restart nginx", ""),
+            Entry::new("nginx logs", "journalctl -u nginx", ""),
+            Entry::new("disk usage", "du -sh", ""),
+        ];
+        let mut options = Options::default();
+
+        // everything is shown without a query
+        assert_eq!(titles(&options, "", &entries).len(), 3);
+        // and nothing that scores zero with one
+        assert_eq!(
+            titles(&options, "nginx", &entries),
+            ["nginx logs", "restart"]
+        );
+
+        options.min_score = 2.0;
+        assert_eq!(titles(&options, "nginx", &entries), ["nginx logs"]);
+    }
+
+    #[test]
+    fn weights() {
+        let entries = [
+            Entry::new("nginx", "systemctl status", ""),
+            Entry::new("reload the web server", "nginx -s reload", ""),
+        ];
+        let mut options = Options {
+            algorithm: Algorithm::Subsequence,
+            ..Options::default()
+        };
+        assert_eq!(
+            titles(&options, "nginx", &entries),
+            ["nginx", "reload the web server"]
+        );
+
+        options.weights = Weights {
+            title: 0.5,
+            description: 1.0,
+            code: 4.0,
+            ..Weights::default()
+        };
+        assert_eq!(
+            titles(&options, "nginx", &entries),
+            ["reload the web server", "nginx"]
+        );
+    }
+
+    #[test]
+    fn boosts_context() {
+        let dir = env::temp_dir();
+        let environment = Environment::at(dir.clone());
+        let in_dir = |entry| Entry {
+            contexts: vec![Context::Dir(dir.to_string_lossy().into_owned())],
+            ..entry
+        };
+        let entries = [
+            Entry::new("cargo build", "cargo build", ""),
+            in_dir(Entry::new("npm build", "npm run build", "")),
+            in_dir(Entry::new("gradle", "gradle build", "")),
+            Entry::new("make", "make", ""),
+        ];
+        let titles = |query| {
+            rank(
+                &Options::default(),
+                &environment,
+                query,
+                &entries,
+                &Bm25Index::new(&entries),
+            )
+            .into_iter()
+            .map(|m| (entries[m.index].title.as_str(), m.in_context))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            titles(""),
+            [
+                ("npm build", true),
+                ("gradle", true),
+                ("cargo build", false),
+                ("make", false)
+            ]
+        );
+        assert_eq!(titles("build")[0], ("npm build", true));
+    }
+
+    #[test]
+    fn directory_history() {
+        let dir = env::temp_dir();
+        let environment = Environment::at(dir.clone());
+        let used = |entry, dir, days_ago: u64| Entry {
+            history: vec![Usage {
+                dir,
+                time: db::unix_time() - days_ago * 24 * 60 * 60,
+            }],
+            ..entry
+        };
+        let entries = [
+            Entry::new("never used", "make", ""),
+            used(
+                Entry::new("used elsewhere", "make", ""),
+                "/elsewhere".into(),
+                0,
+            ),
+            used(Entry::new("used long ago", "make", ""), dir.clone(), 30),
+            used(Entry::new("used today", "make", ""), dir.clone(), 0),
+        ];
+        let titles = |scope| {
+            let options = Options {
+                scope,
+                ..Options::default()
             };
-            (i, title_cmp * 2.0 + desc_cmp + ans_cmp * 1.5)
-        })
-        .collect_vec();
-    matches.sort_by(|a, b| b.1.total_cmp(&a.1));
-    matches
+            rank(
+                &options,
+                &environment,
+                "make",
+                &entries,
+                &Bm25Index::new(&entries),
+            )
+            .into_iter()
+            .map(|m| entries[m.index].title.as_str())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            titles(Scope::Global),
+            [
+                "used today",
+                "never used",
+                "used elsewhere",
+                "used long ago"
+            ]
+        );
+        assert_eq!(titles(Scope::Directory), ["used today", "used long ago"]);
+    }
+
+    #[test]
+    fn tolerates_typos_and_synonyms() {
+        let entries = [
+            Entry::new("git rename branch", "git branch -m [#name]", ""),
+            Entry::new("git delete branch", "git branch -d [#branch]", ""),
+            Entry::new("list files", "ls -la", ""),
+        ];
+        for algorithm in [Algorithm::Trigram, Algorithm::Subsequence] {
+            let options = Options {
+                algorithm,
+                ..Options::default()
+            };
+            for query in ["delte branch", "remove branch", "erase brnach"] {
+                let titles = titles(&options, query, &entries);
+                assert_eq!(titles[0], "git delete branch", "{algorithm:?} {query}");
+            }
+
+            // a synonym scores less than the word that was typed
+            let mut entries = entries.to_vec();
+            entries.push(Entry::new(
+                "git remove branch",
+                "git branch -D [#branch]",
+                "",
+            ));
+            let titles = titles(&options, "remove branch", &entries);
+            assert_eq!(
+                titles[..2],
+                ["git remove branch", "git delete branch"],
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn natural_language() {
+        let entries = [
+            Entry::new("git log", "git log --oneline", "show the commit history"),
+            Entry::new(
+                "git reset",
+                "git reset --soft HEAD~1",
+                "undo the last commit",
+            ),
+            Entry::new("list files", "ls -la", ""),
+        ];
+        let query = "how do I undo my last commits";
+        for algorithm in [Algorithm::Trigram, Algorithm::Subsequence] {
+            let options = Options {
+                algorithm,
+                ..Options::default()
+            };
+            let titles = titles(&options, query, &entries);
+            assert_eq!(titles[..2], ["git reset", "git log"], "{algorithm:?}");
+        }
+
+        // not every word can be found in order
+        let mut options = Options {
+            algorithm: Algorithm::Subsequence,
+            ..Options::default()
+        };
+        options.weights.bm25 = 0.0;
+        assert!(titles(&options, query, &entries).is_empty());
+    }
+
+    #[test]
+    fn narrows() {
+        let entries = [
+            Entry::new("git status", "git status", ""),
+            Entry::new("git stash", "git stash", ""),
+            Entry::new("show processes", "ps", ""),
+        ];
+        let options = Options {
+            algorithm: Algorithm::Subsequence,
+            ..Options::default()
+        };
+        let index = Bm25Index::new(&entries);
+        let mut searcher =
+            Searcher::new(options.clone(), &Environment::default(), &entries, &index);
+
+        let mut rank = |query| {
+            let matches = searcher.rank(query, || false).unwrap();
+            let narrowed = searcher.previous.as_ref().unwrap().1.len();
+            assert_eq!(
+                matches,
+                rank(
+                    &options,
+                    &Environment::default(),
+                    query,
+                    &entries,
+                    &Bm25Index::new(&entries),
+                ),
+                "{query}"
+            );
+            narrowed
+        };
+        assert_eq!(rank("s"), 3);
+        assert_eq!(rank("st"), 2);
+        assert_eq!(rank("stu"), 1);
+        // removing characters doesn't narrow
+        assert_eq!(rank("st"), 2);
+        assert_eq!(rank("st !sta"), 0);
+        // and neither does adding to a negated term
+        assert_eq!(rank("st !stas"), 1);
+        assert_eq!(rank(""), 3);
+    }
+
+    #[test]
+    fn narrows_with_typos_and_synonyms() {
+        let entries = [
+            Entry::new("rename branch", "git branch -m", ""),
+            Entry::new("delete files", "rm -f", ""),
+        ];
+        let options = Options {
+            algorithm: Algorithm::Subsequence,
+            ..Options::default()
+        };
+        let index = Bm25Index::new(&entries);
+        let mut searcher =
+            Searcher::new(options.clone(), &Environment::default(), &entries, &index);
+
+        let mut rank = |query| {
+            let matches = searcher.rank(query, || false).unwrap();
+            assert_eq!(
+                matches,
+                rank(
+                    &options,
+                    &Environment::default(),
+                    query,
+                    &entries,
+                    &Bm25Index::new(&entries),
+                ),
+                "{query}"
+            );
+            matches.len()
+        };
+        // a misspelling can match entries that weren't matched before it
+        // was complete
+        assert_eq!(rank("brna"), 0);
+        assert_eq!(rank("brnac"), 0);
+        assert_eq!(rank("brnach"), 1);
+        // and so can a word once it has synonyms
+        assert_eq!(rank("era"), 1);
+        assert_eq!(rank("erase"), 2);
+    }
+
+    #[test]
+    fn cancels() {
+        let entries = vec![Entry::new("git status", "git status", ""); 1000];
+        let index = Bm25Index::new(&entries);
+        let mut searcher = Searcher::new(
+            Options::default(),
+            &Environment::default(),
+            &entries,
+            &index,
+        );
+        assert!(searcher.rank("git", || true).is_none());
+        assert_eq!(searcher.rank("git", || false).unwrap().len(), 1000);
+    }
 }
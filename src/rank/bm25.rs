@@ -0,0 +1,180 @@
+//! Okapi BM25 over the words of entries, which finds entries for queries
+//! in natural language like "how do I undo the last commit", where fuzzy
+//! matching every word is too strict.
+
+use std::collections::HashMap;
+
+use itertools::Itertools as _;
+
+use crate::db::Entry;
+
+/// How quickly more occurrences of a word stop adding to the score.
+const K1: f32 = 1.2;
+/// How much longer entries are penalized.
+const B: f32 = 0.75;
+
+/// Words that are too common in questions to say anything about entries.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "me", "my", "of", "on", "or", "some", "that", "the", "this", "to",
+    "what", "when", "where", "which", "with", "you",
+];
+
+/// The words of the title, description and tags of every entry.
+#[derive(Debug, Clone, Default)]
+pub struct Bm25Index {
+    /// In the same order as the entries.
+    documents: Vec<Document>,
+    /// Number of entries each word appears in.
+    frequencies: HashMap<String, usize>,
+    /// Number of words in all the entries.
+    total_len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Document {
+    /// Number of times each word appears.
+    words: HashMap<String, u32>,
+    len: usize,
+}
+
+impl Bm25Index {
+    pub fn new(entries: &[Entry]) -> Self {
+        let mut index = Self::default();
+        for entry in entries {
+            index.push(entry);
+        }
+        index
+    }
+
+    pub fn push(&mut self, entry: &Entry) {
+        let document = self.add_document(entry);
+        self.documents.push(document);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        let document = self.documents.remove(index);
+        self.remove_document(&document);
+    }
+
+    pub fn replace(&mut self, index: usize, entry: &Entry) {
+        let document = self.add_document(entry);
+        let old = std::mem::replace(&mut self.documents[index], document);
+        self.remove_document(&old);
+    }
+
+    /// Scores each entry against the words of a query, where entries
+    /// without any of the words score zero.
+    pub fn scores(&self, query: &[String]) -> Vec<f32> {
+        let mut scores = vec![0.0; self.documents.len()];
+        if self.documents.is_empty() {
+            return scores;
+        }
+        let count = self.documents.len() as f32;
+        let average_len = self.total_len as f32 / count;
+
+        for word in query.iter().unique() {
+            let Some(&frequency) = self.frequencies.get(word) else {
+                continue;
+            };
+            let frequency = frequency as f32;
+            let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+            for (score, document) in scores.iter_mut().zip(&self.documents) {
+                let Some(&occurrences) = document.words.get(word) else {
+                    continue;
+                };
+                let occurrences = occurrences as f32;
+                let len_ratio = document.len as f32 / average_len.max(1.0);
+                *score +=
+                    idf * occurrences * (K1 + 1.0) / (occurrences + K1 * (1.0 - B + B * len_ratio));
+            }
+        }
+        scores
+    }
+
+    fn add_document(&mut self, entry: &Entry) -> Document {
+        let mut document = Document::default();
+        let text = [&entry.title, &entry.description]
+            .into_iter()
+            .chain(&entry.tags);
+        for word in text.flat_map(|t| words(t)) {
+            *document.words.entry(word).or_default() += 1;
+            document.len += 1;
+        }
+        for word in document.words.keys() {
+            *self.frequencies.entry(word.clone()).or_default() += 1;
+        }
+        self.total_len += document.len;
+        document
+    }
+
+    fn remove_document(&mut self, document: &Document) {
+        for word in document.words.keys() {
+            if let Some(frequency) = self.frequencies.get_mut(word) {
+                *frequency -= 1;
+                if *frequency == 0 {
+                    self.frequencies.remove(word);
+                }
+            }
+        }
+        self.total_len -= document.len;
+    }
+}
+
+/// Splits text into lowercase words without stop words, and with plurals
+/// made singular so "commits" finds "commit".
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .map(|w| match w.strip_suffix('s') {
+            Some(singular) if singular.len() >= 3 && !singular.ends_with('s') => {
+                singular.to_owned()
+            }
+            _ => w,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{words, Bm25Index};
+    use crate::db::Entry;
+
+    fn query(text: &str) -> Vec<String> {
+        words(text).collect()
+    }
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(
+            query("How do I undo the last commits?"),
+            ["undo", "last", "commit"]
+        );
+        assert_eq!(query("ls -la /tmp/files"), ["ls", "la", "tmp", "file"]);
+        assert_eq!(query("access"), ["access"]);
+    }
+
+    #[test]
+    fn scores() {
+        let entries = [
+            Entry::new("git reset", "git reset HEAD~1", "undo the last commit"),
+            Entry::new("git log", "git log", "show the commit history"),
+            Entry::new("list files", "ls", "").with_tags(["fs"]),
+        ];
+        let mut index = Bm25Index::new(&entries);
+        let scores = index.scores(&query("how do I undo my last commit"));
+        assert!(scores[0] > scores[1] && scores[1] > 0.0);
+        assert_eq!(scores[2], 0.0);
+        assert!(index.scores(&query("fs"))[2] > 0.0);
+
+        // updating entries gives the same scores as indexing them again
+        let edited = Entry::new("git revert", "git revert", "undo a commit safely");
+        index.replace(1, &edited);
+        index.remove(2);
+        index.push(&entries[2]);
+        let expected = Bm25Index::new(&[entries[0].clone(), edited, entries[2].clone()]);
+        let query = query("undo commit fs");
+        assert_eq!(index.scores(&query), expected.scores(&query));
+    }
+}
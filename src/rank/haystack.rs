@@ -0,0 +1,37 @@
+/// Text that is searched, with its lowercase form cached so it isn't
+/// recomputed for every query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Text {
+    pub chars: Vec<char>,
+    /// Each character lowercased on its own, so indexes match `chars`.
+    pub lower: Vec<char>,
+    pub lower_string: String,
+}
+
+impl Text {
+    pub fn new(s: &str) -> Self {
+        let chars = s.chars().collect::<Vec<_>>();
+        let lower = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect::<Vec<_>>();
+        Self {
+            lower_string: lower.iter().collect(),
+            chars,
+            lower,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+}
+
+/// The fields of an entry that are searched.
+#[derive(Debug, Clone)]
+pub struct Haystack {
+    pub title: Text,
+    pub description: Text,
+    pub code: Text,
+    pub tags: Vec<Text>,
+}
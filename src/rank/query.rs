@@ -0,0 +1,226 @@
+//! Parsing search queries into terms.
+//!
+//! A query is split into terms on whitespace, where each term can be:
+//!
+//! - `word`: fuzzy matched against every field.
+//! - `"some words"`: matched exactly, ignoring case.
+//! - `^word` or `word$`: matched exactly at the start or end of a field.
+//! - `!word`: excludes entries that contain the word.
+//! - `title:word`, `code:word`, `desc:word` or `tag:word`: only matched
+//!   against that field.
+//!
+//! Prefixes are combined in that order, like `!code:^sudo`.
+
+use super::Text;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub text: Text,
+    /// Field the term is limited to, or `None` for every field.
+    pub field: Option<Field>,
+    pub quoted: bool,
+    /// Whether the term must be at the start of a field.
+    pub prefix: bool,
+    /// Whether the term must be at the end of a field.
+    pub suffix: bool,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Description,
+    Code,
+    Tag,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        Self {
+            terms: tokenize(query).iter().map(|t| Term::parse(t)).collect(),
+        }
+    }
+}
+
+impl Term {
+    fn parse(token: &str) -> Self {
+        let mut term = Self {
+            text: Text::default(),
+            field: None,
+            quoted: false,
+            prefix: false,
+            suffix: false,
+            negated: false,
+        };
+
+        // each prefix is only stripped if something is left after it
+        let mut rest = token;
+        if let Some(r) = rest.strip_prefix('!').filter(|r| !r.is_empty()) {
+            term.negated = true;
+            rest = r;
+        }
+        if let Some((name, r)) = rest.split_once(':') {
+            if let (Some(field), false) = (Field::from_name(name), r.is_empty()) {
+                term.field = Some(field);
+                rest = r;
+            }
+        }
+        if let Some(r) = rest.strip_prefix('^').filter(|r| !r.is_empty()) {
+            term.prefix = true;
+            rest = r;
+        }
+        if let Some(r) = rest.strip_suffix('$').filter(|r| !r.is_empty()) {
+            term.suffix = true;
+            rest = r;
+        }
+        if let Some(r) = rest.strip_prefix('"') {
+            // an unclosed quote goes until the end of the query
+            let r = r.strip_suffix('"').unwrap_or(r);
+            if !r.is_empty() {
+                term.quoted = true;
+                rest = r;
+            }
+        }
+
+        term.text = Text::new(rest);
+        term
+    }
+
+    /// Whether the term has to appear exactly, instead of being fuzzy
+    /// matched.
+    pub fn is_exact(&self) -> bool {
+        self.quoted || self.prefix || self.suffix || self.negated
+    }
+
+    /// Finds the term in some text, ignoring case, returning the indexes of
+    /// the first characters that match.
+    pub fn find(&self, text: &Text) -> Option<Vec<usize>> {
+        let (term, text) = (&self.text.lower, &text.lower);
+        let len = term.len();
+        if len > text.len() {
+            return None;
+        }
+
+        let last = text.len() - len;
+        let start = match (self.prefix, self.suffix) {
+            (true, true) => (text == term).then_some(0),
+            (true, false) => text.starts_with(term).then_some(0),
+            (false, true) => text.ends_with(term).then_some(last),
+            (false, false) => (0..=last).find(|&i| text[i..].starts_with(term)),
+        }?;
+        Some((start..start + len).collect())
+    }
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "title" => Some(Field::Title),
+            "desc" | "description" => Some(Field::Description),
+            "code" => Some(Field::Code),
+            "tag" => Some(Field::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a query on whitespace outside of quotes, keeping the quotes.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, Query, Term, Text};
+
+    fn term(text: &str) -> Term {
+        Term {
+            text: Text::new(text),
+            field: None,
+            quoted: false,
+            prefix: false,
+            suffix: false,
+            negated: false,
+        }
+    }
+
+    #[test]
+    fn parses() {
+        let query = Query::parse(r#"git  !code:^sudo title:"rebase -i" tar$ tag: ! http://x"#);
+        assert_eq!(
+            query.terms,
+            [
+                term("git"),
+                Term {
+                    field: Some(Field::Code),
+                    prefix: true,
+                    negated: true,
+                    ..term("sudo")
+                },
+                Term {
+                    field: Some(Field::Title),
+                    quoted: true,
+                    ..term("rebase -i")
+                },
+                Term {
+                    suffix: true,
+                    ..term("tar")
+                },
+                term("tag:"),
+                term("!"),
+                term("http://x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds() {
+        let anchored = |text: &str, prefix, suffix| Term {
+            prefix,
+            suffix,
+            ..term(text)
+        };
+        assert_eq!(
+            term("Stat").find(&Text::new("git status")),
+            Some(vec![4, 5, 6, 7])
+        );
+        assert_eq!(
+            anchored("git", true, false).find(&Text::new("Git status")),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            anchored("git", false, true).find(&Text::new("status git")),
+            Some(vec![7, 8, 9])
+        );
+        assert_eq!(
+            anchored("git", true, true).find(&Text::new("git status")),
+            None
+        );
+        assert_eq!(term("tar").find(&Text::new("ls")), None);
+    }
+}
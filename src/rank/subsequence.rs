@@ -0,0 +1,131 @@
+use super::{Ranker, Text};
+
+const MATCH: f32 = 16.0;
+/// Bonus for matching after whitespace, or at the start.
+const BOUNDARY_WHITESPACE: f32 = 8.0;
+/// Bonus for matching after a delimiter like `/` or `-`.
+const BOUNDARY_DELIMITER: f32 = 7.0;
+/// Bonus for matching an uppercase letter after a lowercase one, or a
+/// digit after a letter.
+const CAMEL_CASE: f32 = 7.0;
+const CONSECUTIVE: f32 = 4.0;
+/// Multiplier for the bonus of the first character in a word, so words
+/// that start at a boundary are preferred.
+const FIRST_CHAR_MULTIPLIER: f32 = 2.0;
+const GAP_START: f32 = 3.0;
+const GAP_EXTENSION: f32 = 1.0;
+
+/// Requires a term to appear in order in a field, scoring it by where its
+/// characters matched.
+pub struct Subsequence;
+
+impl Ranker for Subsequence {
+    fn score(&self, term: &Text, text: &Text) -> Option<(f32, Vec<usize>)> {
+        fuzzy_match(&term.lower, text)
+    }
+
+    fn narrows(&self) -> bool {
+        true
+    }
+}
+
+/// Finds the best alignment of a lowercase `word` in `text`, returning its
+/// score and the indexes of the matched characters.
+///
+/// Similar to fzf's algorithm, this finds the best score for matching each
+/// character of the word at each position of the text, from the best score
+/// of the previous character either just before it or after a gap.
+fn fuzzy_match(word: &[char], text: &Text) -> Option<(f32, Vec<usize>)> {
+    let (m, n) = (word.len(), text.chars.len());
+    if m == 0 || m > n {
+        return None;
+    }
+    let (lower, text) = (&text.lower, &text.chars);
+    let bonuses = (0..n)
+        .map(|j| bonus(j.checked_sub(1).map(|p| text[p]), text[j]))
+        .collect::<Vec<_>>();
+
+    // scores[i][j] is the best score with `word[i]` matched at `text[j]`,
+    // and from[i][j] is where `word[i - 1]` was matched
+    let mut scores = vec![vec![f32::NEG_INFINITY; n]; m];
+    let mut from = vec![vec![0; n]; m];
+    for j in 0..n {
+        if lower[j] == word[0] {
+            scores[0][j] = MATCH + bonuses[j] * FIRST_CHAR_MULTIPLIER;
+        }
+    }
+    for i in 1..m {
+        // best score of the previous character ending before `j - 1`,
+        // with the gap penalty up to `j`
+        let mut gap = f32::NEG_INFINITY;
+        let mut gap_from = 0;
+        for j in i..n {
+            if j >= 2 {
+                let start = scores[i - 1][j - 2] - GAP_START;
+                if start > gap - GAP_EXTENSION {
+                    gap = start;
+                    gap_from = j - 2;
+                } else {
+                    gap -= GAP_EXTENSION;
+                }
+            }
+            if lower[j] != word[i] {
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1] + CONSECUTIVE;
+            let (best, prev) = if consecutive >= gap {
+                (consecutive, j - 1)
+            } else {
+                (gap, gap_from)
+            };
+            if best.is_finite() {
+                scores[i][j] = best + MATCH + bonuses[j];
+                from[i][j] = prev;
+            }
+        }
+    }
+
+    let (end, &score) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if !score.is_finite() {
+        return None;
+    }
+    let mut positions = vec![end; m];
+    for i in (1..m).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+    Some((score, positions))
+}
+
+/// Bonus for matching `c`, depending on the character before it.
+fn bonus(prev: Option<char>, c: char) -> f32 {
+    match prev {
+        None => BOUNDARY_WHITESPACE,
+        Some(p) if p.is_whitespace() => BOUNDARY_WHITESPACE,
+        Some(p) if !p.is_alphanumeric() && c.is_alphanumeric() => BOUNDARY_DELIMITER,
+        Some(p) if p.is_lowercase() && c.is_uppercase() => CAMEL_CASE,
+        Some(p) if p.is_alphabetic() && c.is_numeric() => CAMEL_CASE,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_match, Text};
+
+    fn positions(word: &str, text: &str) -> Option<Vec<usize>> {
+        let word = word.chars().collect::<Vec<_>>();
+        fuzzy_match(&word, &Text::new(text)).map(|(_, positions)| positions)
+    }
+
+    #[test]
+    fn prefers_boundaries() {
+        assert_eq!(positions("gco", "git checkout"), Some(vec![0, 4, 9]));
+        assert_eq!(positions("ls", "tools ls"), Some(vec![6, 7]));
+        assert_eq!(positions("fb", "fooBar"), Some(vec![0, 3]));
+        assert_eq!(positions("xyz", "git"), None);
+        assert_eq!(positions("", "git"), None);
+    }
+}
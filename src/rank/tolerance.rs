@@ -0,0 +1,231 @@
+//! Matching words that are spelled differently in an entry, either with a
+//! synonym or with a typo.
+
+use std::collections::HashMap;
+
+use super::{Haystack, Text};
+
+/// Groups of words that mean the same thing in commands.
+const BUILTIN_SYNONYMS: &[&[&str]] = &[
+    &["remove", "delete", "rm", "del", "erase"],
+    &["list", "ls", "show"],
+    &["copy", "cp", "duplicate"],
+    &["move", "mv", "rename"],
+    &["directory", "dir", "folder"],
+    &["create", "new"],
+    &["kill", "stop", "terminate"],
+    &["start", "run", "launch"],
+    &["find", "search", "locate"],
+    &["download", "fetch", "pull"],
+    &["upload", "push"],
+    &["extract", "unzip", "untar", "decompress"],
+    &["compress", "zip", "archive"],
+    &["edit", "change", "modify"],
+    &["print", "echo", "display"],
+];
+
+/// Shortest words that can have a typo, as short words are too similar.
+const ONE_TYPO_MIN_LEN: usize = 5;
+const TWO_TYPOS_MIN_LEN: usize = 9;
+
+/// Words that can be searched for each other, all lowercase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Synonyms {
+    words: HashMap<String, Vec<String>>,
+}
+
+impl Default for Synonyms {
+    fn default() -> Self {
+        Self {
+            words: HashMap::new(),
+        }
+        .with_groups(BUILTIN_SYNONYMS.iter().copied())
+    }
+}
+
+impl Synonyms {
+    /// Adds groups of words that mean the same thing, on top of the ones
+    /// that are already there.
+    pub fn with_groups(
+        mut self,
+        groups: impl IntoIterator<Item = impl IntoIterator<Item = impl AsRef<str>>>,
+    ) -> Self {
+        for group in groups {
+            let group = group
+                .into_iter()
+                .map(|w| w.as_ref().trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<_>>();
+            for word in &group {
+                let synonyms = self.words.entry(word.clone()).or_default();
+                for other in &group {
+                    if other != word && !synonyms.contains(other) {
+                        synonyms.push(other.clone());
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// The synonyms of a lowercase word.
+    pub fn of(&self, word: &str) -> &[String] {
+        self.words.get(word).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Number of typos a word can have and still match.
+pub fn max_typos(len: usize) -> usize {
+    if len >= TWO_TYPOS_MIN_LEN {
+        2
+    } else if len >= ONE_TYPO_MIN_LEN {
+        1
+    } else {
+        0
+    }
+}
+
+/// The words of every entry, so the ones a word could be a misspelling of
+/// are found once instead of in every field.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    /// Each lowercase word to the indexes of the entries that have it,
+    /// sorted.
+    words: HashMap<Vec<char>, Vec<usize>>,
+    /// Words that were already looked up, to their corrections.
+    corrections: HashMap<Vec<char>, Vec<Vec<char>>>,
+}
+
+impl Vocabulary {
+    pub fn new(haystacks: &[Haystack]) -> Self {
+        let mut words = HashMap::<_, Vec<usize>>::new();
+        for (i, haystack) in haystacks.iter().enumerate() {
+            for text in [&haystack.title, &haystack.description, &haystack.code] {
+                for word in words_of(text) {
+                    let entries = words.entry(word.to_vec()).or_default();
+                    if entries.last() != Some(&i) {
+                        entries.push(i);
+                    }
+                }
+            }
+        }
+        Self {
+            words,
+            corrections: HashMap::new(),
+        }
+    }
+
+    /// Words of the entries that a lowercase word could be a misspelling of,
+    /// sorted.
+    pub fn corrections(&mut self, word: &[char]) -> &[Vec<char>] {
+        let words = &self.words;
+        self.corrections.entry(word.to_vec()).or_insert_with(|| {
+            let max = max_typos(word.len());
+            if max == 0 {
+                return Vec::new();
+            }
+            let mut corrections = words
+                .keys()
+                .filter(|w| {
+                    *w != word
+                        && w.len().abs_diff(word.len()) <= max
+                        && edit_distance(word, w) <= max
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            corrections.sort_unstable();
+            corrections
+        })
+    }
+
+    /// Indexes of the entries that have a lowercase word, sorted.
+    pub fn entries(&self, word: &[char]) -> &[usize] {
+        self.words.get(word).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Whether a text has a lowercase word.
+pub fn has_word(text: &Text, word: &[char]) -> bool {
+    words_of(text).any(|w| w == word)
+}
+
+fn words_of(text: &Text) -> impl Iterator<Item = &[char]> {
+    text.lower
+        .split(|c| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+}
+
+/// Number of insertions, deletions, substitutions and transpositions of
+/// adjacent characters needed to turn `a` into `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    // rows of distances for the prefixes of `a` against each prefix of `b`
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        before_previous.clone_from(&previous);
+        previous.clone_from(&current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, has_word, Synonyms, Vocabulary};
+    use crate::db::Entry;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn distances() {
+        let distance = |a, b| edit_distance(&chars(a), &chars(b));
+        assert_eq!(distance("delte", "delete"), 1);
+        assert_eq!(distance("dleete", "delete"), 1);
+        assert_eq!(distance("branhc", "branch"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "ls"), 2);
+    }
+
+    #[test]
+    fn corrects() {
+        let haystacks = [
+            Entry::new("Delete a branch", "git delete-branch --force", ""),
+            Entry::new("List branches", "git branch", ""),
+        ]
+        .map(|e| e.to_haystack());
+        let mut vocabulary = Vocabulary::new(&haystacks);
+        let mut corrections = |word| vocabulary.corrections(&chars(word)).to_vec();
+        assert_eq!(corrections("delte"), [chars("delete")]);
+        assert_eq!(corrections("brnach"), [chars("branch")]);
+        assert_eq!(corrections("branchs"), [chars("branch"), chars("branches")]);
+        assert!(corrections("delete").is_empty());
+        // too short to have a typo
+        assert!(corrections("gti").is_empty());
+
+        assert_eq!(vocabulary.entries(&chars("branch")), [0, 1]);
+        assert_eq!(vocabulary.entries(&chars("delete")), [0]);
+        assert!(has_word(&haystacks[0].code, &chars("delete")));
+        assert!(!has_word(&haystacks[1].code, &chars("bran")));
+    }
+
+    #[test]
+    fn synonyms() {
+        let synonyms = Synonyms::default().with_groups([["Deploy", "ship"], ["rm", "nuke"]]);
+        assert!(synonyms.of("remove").contains(&"delete".to_owned()));
+        assert_eq!(synonyms.of("deploy"), ["ship"]);
+        assert!(synonyms.of("rm").contains(&"nuke".to_owned()));
+        assert!(synonyms.of("git").is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use super::{Ranker, Text};
+
+/// Compares the trigrams of a term with a field, so every field matches
+/// to some extent.
+pub struct Trigram;
+
+impl Ranker for Trigram {
+    fn score(&self, term: &Text, text: &Text) -> Option<(f32, Vec<usize>)> {
+        Some(compare(&term.lower, &text.lower))
+    }
+}
+
+/// Scores how many of the trigrams of `term` are in `text`, out of all of
+/// them, with the indexes of the characters in the trigrams that matched.
+///
+/// Both are padded with two spaces before and one after, so the start of
+/// a word counts more than its end, like `rust_fuzzy_search`. Whitespace
+/// isn't included in the positions.
+fn compare(term: &[char], text: &[char]) -> (f32, Vec<usize>) {
+    let term_trigrams = trigrams(term);
+    let text_trigrams = trigrams(text);
+    let mut matched = 0;
+    let mut positions = Vec::new();
+
+    for trigram in &term_trigrams {
+        let Some(start) = text_trigrams.iter().position(|t| t == trigram) else {
+            continue;
+        };
+        matched += 1;
+        // the trigram at `start` ends with the character at `start`, and
+        // starts two characters before it in the padding
+        positions.extend(
+            (start.saturating_sub(2)..=start)
+                .filter(|&i| text.get(i).is_some_and(|c| !c.is_whitespace())),
+        );
+    }
+
+    positions.sort_unstable();
+    positions.dedup();
+    (matched as f32 / term_trigrams.len() as f32, positions)
+}
+
+fn trigrams(s: &[char]) -> Vec<[char; 3]> {
+    let padded = [' ', ' ']
+        .iter()
+        .chain(s)
+        .chain([' '].iter())
+        .copied()
+        .collect::<Vec<_>>();
+    padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, Text};
+
+    #[test]
+    fn positions() {
+        let positions = |term, text| compare(&Text::new(term).lower, &Text::new(text).lower).1;
+        assert_eq!(positions("stat", "git status"), vec![4, 5, 6, 7]);
+        assert_eq!(positions("GS", "git Status"), vec![0]);
+        assert_eq!(positions("a xyz", "a Ba"), vec![0]);
+        assert_eq!(positions("", "git"), Vec::<usize>::new());
+        // only the trigrams that count towards the score are highlighted
+        assert_eq!(positions("gti", "git"), vec![0]);
+    }
+
+    #[test]
+    fn scores() {
+        let score = |term, text| compare(&Text::new(term).lower, &Text::new(text).lower).0;
+        assert_eq!(score("git", "git status"), 1.0);
+        assert_eq!(score("status", "git status"), 6.0 / 7.0);
+        assert_eq!(score("xyz", "git"), 0.0);
+        assert_eq!(score("it", "git"), 1.0 / 3.0);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use super::{Match, Searcher};
+
+/// Builds a searcher on the worker's thread, as it goes through every
+/// entry.
+type Build = Box<dyn FnOnce() -> Searcher + Send>;
+
+enum Request {
+    Rank { generation: u64, query: String },
+    Replace(Build),
+}
+
+/// Ranks queries on a background thread, so typing doesn't wait for the
+/// previous query to be ranked.
+///
+/// Sending a new query cancels the one being ranked, and only the results
+/// of the latest query are returned.
+pub struct Worker {
+    requests: Sender<Request>,
+    results: Receiver<(u64, Vec<Match>)>,
+    generation: Arc<AtomicU64>,
+}
+
+impl Worker {
+    /// Starts a thread that builds a searcher and ranks queries with it.
+    pub fn spawn(searcher: impl FnOnce() -> Searcher + Send + 'static) -> Self {
+        let (requests, request_receiver) = mpsc::channel();
+        let (result_sender, results) = mpsc::channel();
+        let generation = Arc::new(AtomicU64::new(0));
+
+        let current = Arc::clone(&generation);
+        thread::spawn(move || run(searcher(), &request_receiver, &result_sender, &current));
+
+        Self {
+            requests,
+            results,
+            generation,
+        }
+    }
+
+    /// Starts ranking a query, cancelling the previous one.
+    pub fn rank(&self, query: impl Into<String>) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        // the thread only stops when the worker is dropped
+        let _ = self.requests.send(Request::Rank {
+            generation,
+            query: query.into(),
+        });
+    }
+
+    /// Replaces the searcher used for the following queries, for when the
+    /// entries change, cancelling the query being ranked.
+    pub fn replace(&self, searcher: impl FnOnce() -> Searcher + Send + 'static) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let _ = self.requests.send(Request::Replace(Box::new(searcher)));
+    }
+
+    /// Replaces the searcher and starts ranking a query with it.
+    pub fn replace_and_rank(
+        &self,
+        searcher: impl FnOnce() -> Searcher + Send + 'static,
+        query: impl Into<String>,
+    ) {
+        self.replace(searcher);
+        self.rank(query);
+    }
+
+    /// Results of the latest query, if they are ready.
+    pub fn try_recv(&self) -> Option<Vec<Match>> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.results
+            .try_iter()
+            .filter(|(g, _)| *g == generation)
+            .last()
+            .map(|(_, matches)| matches)
+    }
+
+    /// Waits for the results of the latest query.
+    pub fn recv(&self) -> Option<Vec<Match>> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.results
+            .iter()
+            .find(|(g, _)| *g == generation)
+            .map(|(_, matches)| matches)
+    }
+}
+
+fn run(
+    mut searcher: Searcher,
+    requests: &Receiver<Request>,
+    results: &Sender<(u64, Vec<Match>)>,
+    current: &AtomicU64,
+) {
+    while let Ok(request) = requests.recv() {
+        // only the last pending query needs to be ranked, with the last
+        // searcher
+        let mut latest = None;
+        let mut replacement = None;
+        for request in std::iter::once(request).chain(requests.try_iter()) {
+            match request {
+                Request::Rank { generation, query } => latest = Some((generation, query)),
+                Request::Replace(build) => replacement = Some(build),
+            }
+        }
+        if let Some(build) = replacement {
+            searcher = build();
+        }
+        let Some((generation, query)) = latest else {
+            continue;
+        };
+        let is_cancelled = || current.load(Ordering::SeqCst) != generation;
+        if let Some(matches) = searcher.rank(&query, is_cancelled) {
+            if results.send((generation, matches)).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Worker;
+    use crate::{
+        context::Environment,
+        db::Entry,
+        rank::{rank, Bm25Index, Options, Searcher},
+    };
+
+    #[test]
+    fn ranks_latest() {
+        let entries = [
+            Entry::new("git status", "git status", ""),
+            Entry::new("docker ps", "docker ps", ""),
+        ];
+        let (options, environment) = (Options::default(), Environment::default());
+        let searcher = |entries: Vec<Entry>| {
+            let (options, environment) = (options.clone(), environment.clone());
+            move || Searcher::new(options, &environment, &entries, &Bm25Index::new(&entries))
+        };
+        let worker = Worker::spawn(searcher(entries.to_vec()));
+        worker.rank("git");
+        worker.rank("docker");
+        assert_eq!(
+            worker.recv(),
+            Some(rank(
+                &options,
+                &environment,
+                "docker",
+                &entries,
+                &Bm25Index::new(&entries)
+            ))
+        );
+        assert_eq!(worker.try_recv(), None);
+
+        let entries = &entries[..1];
+        worker.replace_and_rank(searcher(entries.to_vec()), "docker");
+        assert_eq!(
+            worker.recv(),
+            Some(rank(
+                &options,
+                &environment,
+                "docker",
+                entries,
+                &Bm25Index::new(entries)
+            ))
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Temporarily pointing stdout at stderr.
+//!
+//! crossterm asks the terminal for the cursor position through stdout,
+//! which is usually piped into the shell when searching from a widget.
+//! This is only needed for the inline viewport, as the full screen one
+//! never asks for the cursor position.
+
+use std::{
+    io,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+/// Duplicate of the original stdout, or -1 if it isn't redirected.
+static SAVED_STDOUT: AtomicI32 = AtomicI32::new(-1);
+
+/// Points stdout at stderr until [`restore_stdout`] is called.
+#[cfg(unix)]
+pub fn stdout_to_stderr() -> io::Result<()> {
+    if SAVED_STDOUT.load(Ordering::SeqCst) >= 0 {
+        return Ok(());
+    }
+    // SAFETY: only duplicates the standard file descriptors, which stay
+    // open for the whole program.
+    unsafe {
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            libc::close(saved);
+            return Err(io::Error::last_os_error());
+        }
+        SAVED_STDOUT.store(saved, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn stdout_to_stderr() -> io::Result<()> {
+    Ok(())
+}
+
+/// Points stdout back at the original stdout, if it was redirected.
+#[cfg(unix)]
+pub fn restore_stdout() -> io::Result<()> {
+    let saved = SAVED_STDOUT.swap(-1, Ordering::SeqCst);
+    if saved < 0 {
+        return Ok(());
+    }
+    // SAFETY: `saved` is a descriptor duplicated by `stdout_to_stderr`,
+    // which is only closed here.
+    unsafe {
+        let result = libc::dup2(saved, libc::STDOUT_FILENO);
+        libc::close(saved);
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn restore_stdout() -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Scripts for integrating with each shell.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    /// Nushell.
+    Nu,
+    /// PowerShell.
+    Pwsh,
+}
+
+/// What the search widget does with the selected command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WidgetMode {
+    /// Inserts the command at the cursor.
+    #[default]
+    Insert,
+    /// Replaces the whole line.
+    Replace,
+    /// Searches with the word under the cursor, replacing it.
+    ReplaceWord,
+    /// Searches with the whole line, replacing it.
+    Query,
+}
+
+impl Shell {
+    fn file_name(self) -> &'static str {
+        match self {
+            Shell::Zsh => "integration.zsh",
+            Shell::Bash => "integration.bash",
+            Shell::Fish => "integration.fish",
+            Shell::Nu => "integration.nu",
+            Shell::Pwsh => "integration.ps1",
+        }
+    }
+
+    /// Script defining the widget that inserts a command.
+    fn script(self) -> &'static str {
+        match self {
+            Shell::Zsh => include_str!("../shell/integration.zsh"),
+            Shell::Bash => include_str!("../shell/integration.bash"),
+            Shell::Fish => include_str!("../shell/integration.fish"),
+            Shell::Nu => include_str!("../shell/integration.nu"),
+            Shell::Pwsh => include_str!("../shell/integration.ps1"),
+        }
+    }
+
+    /// Script defining the widget that saves the previous command and the
+    /// name of the widget, if the shell supports it.
+    fn save_last_script(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Shell::Zsh => Some((
+                include_str!("../shell/save-last.zsh"),
+                "how-save-last-widget",
+            )),
+            Shell::Bash => Some((
+                include_str!("../shell/save-last.bash"),
+                "__how_save_last_widget__",
+            )),
+            Shell::Fish => Some((
+                include_str!("../shell/save-last.fish"),
+                "how-save-last-widget",
+            )),
+            Shell::Nu | Shell::Pwsh => None,
+        }
+    }
+
+    /// Name of the search widget for a mode, if the shell supports it.
+    pub fn widget_name(self, mode: WidgetMode) -> Option<&'static str> {
+        match (self, mode) {
+            (Shell::Zsh | Shell::Fish | Shell::Nu, WidgetMode::Insert) => Some("how-widget"),
+            (Shell::Bash, WidgetMode::Insert) => Some("__how_widget__"),
+            (Shell::Pwsh, WidgetMode::Insert) => Some("Invoke-HowWidget"),
+            (Shell::Zsh, WidgetMode::Replace) => Some("how-replace-widget"),
+            (Shell::Zsh, WidgetMode::ReplaceWord) => Some("how-replace-word-widget"),
+            (Shell::Zsh, WidgetMode::Query) => Some("how-query-widget"),
+            _ => None,
+        }
+    }
+
+    /// Ctrl-_ in the shell's key syntax.
+    pub fn default_key(self) -> &'static str {
+        match self {
+            Shell::Zsh => "^_",
+            Shell::Bash => r"\C-_",
+            Shell::Fish => r"\c_",
+            // terminals send Ctrl-_ as the same byte as Ctrl-7
+            Shell::Nu => "control+char_7",
+            Shell::Pwsh => "Ctrl+_",
+        }
+    }
+
+    /// Ctrl-X Ctrl-_ in the shell's key syntax, if the shell supports
+    /// saving the previous command.
+    pub fn default_save_last_key(self) -> Option<&'static str> {
+        match self {
+            Shell::Zsh => Some("^X^_"),
+            Shell::Bash => Some(r"\C-x\C-_"),
+            Shell::Fish => Some(r"\cx\c_"),
+            Shell::Nu | Shell::Pwsh => None,
+        }
+    }
+
+    /// The integration script to be sourced by the shell.
+    ///
+    /// Keys are written in the shell's own syntax, and `widget` should be
+    /// from [`Shell::widget_name`]. The save last command widget is only
+    /// included if a key is given for it, and is ignored if the shell does
+    /// not support it.
+    pub fn integration(self, key: &str, widget: &str, save_last_key: Option<&str>) -> String {
+        let name = self.file_name();
+        let mut script = format!("### start: how {name} ###\n{}\n", self.script());
+        script.push_str(&self.bind(key, widget));
+
+        if let (Some((save_last, widget)), Some(save_last_key)) =
+            (self.save_last_script(), save_last_key)
+        {
+            _ = writeln!(script, "\n{save_last}");
+            script.push_str(&self.bind(save_last_key, widget));
+        }
+
+        _ = writeln!(script, "### end: how {name} ###");
+        script
+    }
+
+    /// Commands to bind a key to a widget.
+    fn bind(self, key: &str, widget: &str) -> String {
+        match self {
+            Shell::Zsh => format!("bindkey '{key}' {widget}\n"),
+            Shell::Bash => format!(
+                "for keymap in emacs-standard vi-command vi-insert; do
+  bind -m \"$keymap\" -x '\"{key}\": {widget}'
+done
+unset keymap
+"
+            ),
+            Shell::Fish => format!(
+                "bind {key} {widget}
+if bind -M insert > /dev/null 2>&1
+  bind -M insert {key} {widget}
+end
+"
+            ),
+            Shell::Nu => {
+                let (modifier, keycode) = key.rsplit_once('+').unwrap_or(("none", key));
+                format!(
+                    "$env.config = ($env.config | upsert keybindings ($env.config.keybindings | append {{
+  name: {name}
+  modifier: {modifier}
+  keycode: {keycode}
+  mode: [emacs, vi_normal, vi_insert]
+  event: {{ send: executehostcommand, cmd: \"{widget}\" }}
+}}))
+",
+                    name = widget.replace('-', "_"),
+                )
+            }
+            Shell::Pwsh => format!(
+                "Set-PSReadLineKeyHandler -Chord '{key}' -BriefDescription '{widget}' -ScriptBlock {{ {widget} }}\n"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shell, WidgetMode};
+
+    #[test]
+    fn integrations() {
+        for (shell, binding) in [
+            (Shell::Zsh, "bindkey '^_' how-widget\n"),
+            (
+                Shell::Bash,
+                r#"bind -m "$keymap" -x '"\C-_": __how_widget__'"#,
+            ),
+            (Shell::Fish, "bind \\c_ how-widget\n"),
+            (Shell::Nu, "modifier: control\n  keycode: char_7\n"),
+            (Shell::Pwsh, "-Chord 'Ctrl+_'"),
+        ] {
+            let widget = shell.widget_name(WidgetMode::Insert).unwrap();
+            let script = shell.integration(shell.default_key(), widget, None);
+            assert!(script.starts_with(&format!("### start: how {} ###\n", shell.file_name())));
+            assert!(script.ends_with(&format!("### end: how {} ###\n", shell.file_name())));
+            assert!(script.contains(binding), "{shell:?} does not bind the key");
+            assert!(!script.contains("save-last") && !script.contains("save_last"));
+        }
+    }
+
+    #[test]
+    fn custom_keys() {
+        let script = Shell::Zsh.integration("^G", "how-widget", Some("^X^G"));
+        assert!(script.contains("bindkey '^G' how-widget\n"));
+        assert!(script.contains("bindkey '^X^G' how-save-last-widget\n"));
+        assert!(!script.contains("'^_'"));
+
+        let script = Shell::Pwsh.integration("Ctrl+g", "Invoke-HowWidget", Some("Ctrl+h"));
+        assert!(script.contains("-Chord 'Ctrl+g'"));
+        assert!(!script.contains("Ctrl+h"));
+    }
+
+    #[test]
+    fn widget_modes() {
+        let widget = Shell::Zsh.widget_name(WidgetMode::Query).unwrap();
+        let script = Shell::Zsh.integration("^_", widget, None);
+        assert!(script.contains("how-query-widget() {"));
+        assert!(script.contains("bindkey '^_' how-query-widget\n"));
+
+        assert_eq!(Shell::Bash.widget_name(WidgetMode::ReplaceWord), None);
+    }
+}
@@ -27,8 +27,9 @@
 //! - Multiple templates can have the same number, in which case both will
 //!   be selected and edited at the same time.
 
-use std::{collections::HashMap, mem, ops::Range};
+use std::{borrow::Cow, collections::HashMap, mem, ops::Range};
 
+use clap::ValueEnum;
 use ir::{IncrementalU8, State};
 use itertools::Itertools;
 use thiserror::Error;
@@ -104,7 +105,92 @@ pub enum Error {
     TooManyFields,
 }
 
-fn parse(s: &str) -> Result<TemplatedCommand, Error> {
+/// Rules for quoting the values filled into inputs, so that each value is
+/// a single shell word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Quoting {
+    /// POSIX shells like sh, bash and zsh.
+    #[default]
+    Posix,
+    /// fish, which allows escaping quotes within single quotes.
+    Fish,
+}
+
+impl Quoting {
+    /// Quotes a value if it contains any characters special to the shell.
+    ///
+    /// Empty values are left empty, instead of being an empty quoted word.
+    /// Quoting keeps the value expanded like it would be without quotes: a
+    /// leading `~/` is left outside, and values with variables or command
+    /// substitutions are double quoted.
+    pub fn quote(self, s: &str) -> Cow<'_, str> {
+        if is_shell_safe(s) {
+            return Cow::Borrowed(s);
+        }
+
+        // `~` and `~user` are only expanded when they aren't quoted
+        let (home, rest) = match s.find('/') {
+            Some(slash) if s.starts_with('~') && is_shell_safe(&s[1..slash]) => {
+                s.split_at(slash + 1)
+            }
+            None if s.starts_with('~') && is_shell_safe(&s[1..]) => return Cow::Borrowed(s),
+            _ => ("", s),
+        };
+        if is_shell_safe(rest) {
+            return Cow::Borrowed(s);
+        }
+
+        let quoted = if rest.contains(['$', '`']) {
+            let escaped = rest.replace('\\', r"\\").replace('"', r#"\""#);
+            format!("\"{escaped}\"")
+        } else {
+            let escaped = match self {
+                Quoting::Posix => rest.replace('\'', r"'\''"),
+                Quoting::Fish => rest.replace('\\', r"\\").replace('\'', r"\'"),
+            };
+            format!("'{escaped}'")
+        };
+        Cow::Owned(format!("{home}{quoted}"))
+    }
+}
+
+/// Whether a string has no characters special to the shell, so it doesn't
+/// need to be quoted.
+pub fn is_shell_safe(s: &str) -> bool {
+    s.chars()
+        .all(|c| c.is_alphanumeric() || "-_./:=@%+,".contains(c))
+}
+
+/// Escapes a string so that it is parsed as a literal, without any inputs.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '[' | ']' | '#' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Undoes [`escape`], turning escaped characters back into what they
+/// escaped.
+pub fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next_if(|c| matches!(c, '[' | ']' | '#' | '\\')) {
+                unescaped.push(escaped);
+                continue;
+            }
+        }
+        unescaped.push(c);
+    }
+    unescaped
+}
+
+pub fn parse(s: &str) -> Result<TemplatedCommand, Error> {
     let mut is_escaped = false;
     // buffer characters to push after escapes are handled
     let mut to_push = None;
@@ -116,11 +202,25 @@ fn parse(s: &str) -> Result<TemplatedCommand, Error> {
     let mut template = TemplatedCommand::default();
 
     for c in s.chars() {
+        // prev character was a `\`, did not escape anything
+        if is_escaped && !matches!(c, '[' | ']' | '#' | '\\') {
+            is_escaped = false;
+            match &mut input_state {
+                State::Literal(..) | State::Default(..) => template.display.push('\\'),
+                State::Description(.., desc) => desc.push('\\'),
+                State::Index(..) => return Err(Error::InvalidNumber),
+            }
+        }
+
         match (c, &mut input_state) {
             // handle escape characters
             ('[' | ']' | '#' | '\\', _) if is_escaped => {
                 is_escaped = false;
-                to_push = Some(c);
+                match &mut input_state {
+                    State::Literal(..) | State::Default(..) => to_push = Some(c),
+                    State::Description(.., desc) => desc.push(c),
+                    State::Index(..) => return Err(Error::InvalidNumber),
+                }
             }
             ('\\', _) => {
                 is_escaped = true;
@@ -189,14 +289,23 @@ fn parse(s: &str) -> Result<TemplatedCommand, Error> {
             (_, State::Description(.., ref mut s)) => s.push(c),
         }
 
-        // prev character was a `\`, did not escape anything
-        if is_escaped {
-            template.display.push('\\');
-            is_escaped = false;
-        }
         template.display.extend(to_push.take());
     }
 
+    // trailing backslash, did not escape anything
+    if is_escaped {
+        template.display.push('\\');
+    }
+    match input_state {
+        State::Literal(start) => {
+            let literal_range = start..template.display.len();
+            if !literal_range.is_empty() {
+                template.push_literal(literal_range);
+            }
+        }
+        _ => return Err(Error::UnbalancedBrackets),
+    }
+
     // reverse for efficient popping from the left
     unassigned_inputs.reverse();
 
@@ -243,6 +352,56 @@ pub struct TemplatedCommand {
 }
 
 impl TemplatedCommand {
+    /// The command with all inputs replaced by their default values.
+    pub fn display(&self) -> String {
+        self.display.iter().collect()
+    }
+
+    /// The command with every input replaced by the output of `f`, given the
+    /// input's default value and description.
+    pub fn map_inputs(&self, mut f: impl FnMut(&str, &str) -> String) -> String {
+        self.map_numbered_inputs(|default, desc, _| f(default, desc))
+    }
+
+    /// Like [`map_inputs`](Self::map_inputs), also giving `f` the number of
+    /// the input in the order they are jumped to, which is the same for
+    /// inputs that are edited together.
+    pub fn map_numbered_inputs(&self, mut f: impl FnMut(&str, &str, usize) -> String) -> String {
+        let mut numbers = vec![0; self.sections.len()];
+        for (number, sections) in self.input_order.iter().enumerate() {
+            for &section in sections {
+                numbers[section] = number;
+            }
+        }
+        self.sections
+            .iter()
+            .zip(numbers)
+            .map(|(section, number)| match section {
+                TemplateSection::Literal(range) => self.text(range.clone()),
+                TemplateSection::Input(range, desc) => f(&self.text(range.clone()), desc, number),
+            })
+            .collect()
+    }
+
+    /// The command with every input filled with its default value, quoted
+    /// as a single word.
+    pub fn fill_defaults(&self, quoting: Quoting) -> String {
+        self.map_inputs(|default, _| quoting.quote(default).into_owned())
+    }
+
+    /// The default value and description of every input, in the order
+    /// they appear.
+    pub fn inputs(&self) -> impl Iterator<Item = (String, &str)> {
+        self.sections.iter().filter_map(|section| match section {
+            TemplateSection::Literal(_) => None,
+            TemplateSection::Input(range, desc) => Some((self.text(range.clone()), &**desc)),
+        })
+    }
+
+    fn text(&self, range: Range<usize>) -> String {
+        self.display[range].iter().collect()
+    }
+
     pub fn push_input(&mut self, range: Range<usize>, description: String) {
         self.sections
             .push(TemplateSection::Input(range, description));
@@ -255,10 +414,56 @@ impl TemplatedCommand {
 
 #[cfg(test)]
 mod tests {
-    use super::parse;
+    use super::{escape, parse, unescape, Quoting};
 
     #[test]
     pub fn works() {
         _ = dbg!(parse("git diff [main#from#1]..[#to]"));
     }
+
+    #[test]
+    pub fn escape_is_literal() {
+        let s = r"[[ -f a#b ]] && echo \[\\";
+        let template = parse(&escape(s)).unwrap();
+        assert_eq!(template.display(), s);
+        assert_eq!(template.input_order.len(), 0);
+        assert_eq!(unescape(&escape(s)), s);
+        assert_eq!(unescape(r"a\n\"), r"a\n\");
+    }
+
+    #[test]
+    pub fn map_inputs() {
+        let template = parse(r"git diff [main#from#1]..[#to] -- [#a\#b] end\").unwrap();
+        assert_eq!(template.display(), r"git diff main.. --  end\");
+        assert_eq!(
+            template.map_inputs(|default, desc| format!("<{default}|{desc}>")),
+            r"git diff <main|from>..<|to> -- <|a#b> end\"
+        );
+
+        let template = parse("cp [#a#2] [b] [#a#2] [c#c#1]").unwrap();
+        assert_eq!(
+            template.map_numbered_inputs(|default, _, number| format!("{default}{number}")),
+            "cp 1 b2 1 c0"
+        );
+    }
+
+    #[test]
+    pub fn quoting() {
+        let template = parse(r"echo [it's here#a] [a\b#b] [plain#c] [#d]").unwrap();
+        assert_eq!(
+            template.fill_defaults(Quoting::Posix),
+            r"echo 'it'\''s here' 'a\b' plain "
+        );
+        assert_eq!(
+            template.fill_defaults(Quoting::Fish),
+            r"echo 'it\'s here' 'a\\b' plain "
+        );
+
+        // quoting doesn't stop expansions
+        let template = parse(r"cd [~/src#a] [~/my dir#b] [~#c] [$HOME/my dir#d]").unwrap();
+        assert_eq!(
+            template.fill_defaults(Quoting::Posix),
+            r#"cd ~/src ~/'my dir' ~ "$HOME/my dir""#
+        );
+    }
 }
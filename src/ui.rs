@@ -1,21 +1,27 @@
 use std::{cell::RefCell, cmp, num::Saturating, rc::Rc};
 
+use clap::ValueEnum;
 use color_eyre::Result;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::Stylize,
-    widgets::{StatefulWidget, Widget},
+    widgets::{Block, Paragraph, StatefulWidget, Widget, Wrap},
 };
 use ratatui_macros::{horizontal, line, vertical};
 use tui_textarea::{Input, Key};
 use tui_widget_list::{ListBuilder, ListState, ListView};
 
 use crate::{
-    db::{Data, Entry},
-    rank,
+    context::Environment,
+    danger,
+    db::{Data, Entry, EntryPreview, Usage},
+    history::QueryHistory,
+    import,
+    rank::{self, Match, Scope, Searcher, Worker},
+    template::{self, Quoting},
     utils::Wrapping,
-    widgets::{ConfirmDialog, TextArea},
+    widgets::{highlight, ConfirmDialog, Picker, TextArea},
 };
 
 pub enum AppControl {
@@ -32,22 +38,129 @@ impl AppControl {
 pub struct App {
     data: Rc<RefCell<Data>>,
     query: TextArea,
-    matches: Vec<(usize, f32)>,
+    matches: Vec<Match>,
     list_index: Saturating<usize>,
     entry_editor: Option<EntryEditor>,
     dialog: Option<ConfirmDialog<Self>>,
+    /// Whether the app only shows the entry editor, exiting when it closes.
+    only_editor: bool,
+    quoting: Quoting,
+    rank_options: rank::Options,
+    environment: Environment,
+    /// Ranks queries as they are typed, which is only started when the
+    /// search is used, once the app is configured.
+    worker: Option<Worker>,
+    /// Whether the worker is ranking a query that hasn't been shown yet.
+    ranking: bool,
+    /// Whether dangerous commands have to be confirmed before being
+    /// selected.
+    confirm_dangerous: bool,
+    /// Command that has been confirmed, to be returned after the dialog
+    /// closes.
+    confirmed_command: Option<String>,
+    history: Option<QueryHistory>,
+    /// How far back in the history the query is while going through it
+    /// with Ctrl-P and Ctrl-N, 0 being the most recent query, and the query
+    /// that was typed before.
+    history_position: Option<(usize, String)>,
+    history_search: Option<Picker>,
 }
 
 impl App {
     pub fn new(data: Data, initial_query: impl Into<String>) -> Self {
-        let initial_query = initial_query.into();
         Self {
-            matches: rank::rank(&initial_query, data.entries()),
+            matches: Vec::new(),
+            worker: None,
+            ranking: false,
             data: Rc::new(RefCell::new(data)),
-            query: TextArea::new_focused(initial_query, "Search").set_single_line(),
+            query: TextArea::new_focused(initial_query, scope_title(Scope::Global))
+                .set_single_line(),
             list_index: Saturating(0),
             entry_editor: None,
             dialog: None,
+            only_editor: false,
+            quoting: Quoting::default(),
+            rank_options: rank::Options::default(),
+            environment: Environment::default(),
+            confirm_dangerous: false,
+            confirmed_command: None,
+            history: None,
+            history_position: None,
+            history_search: None,
+        }
+    }
+
+    pub fn set_quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    pub fn set_rank_options(mut self, rank_options: rank::Options) -> Self {
+        self.query.set_title(scope_title(rank_options.scope));
+        self.rank_options = rank_options;
+        self.reload_entries();
+        self
+    }
+
+    /// Sets where `how` was launched, to boost entries with an active
+    /// context.
+    pub fn set_environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self.reload_entries();
+        self
+    }
+
+    pub fn set_confirm_dangerous(mut self, confirm_dangerous: bool) -> Self {
+        self.confirm_dangerous = confirm_dangerous;
+        self
+    }
+
+    /// Sets the previous queries, that can be searched again and that the
+    /// query is added to with [`App::record_query`].
+    pub fn set_history(mut self, history: QueryHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Adds the current query to the history, once a command is accepted.
+    pub fn record_query(&mut self) -> Result<()> {
+        let query = self.query_text().to_owned();
+        match &mut self.history {
+            Some(history) => history.add(&query),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates an app that only adds a new entry, starting from the fields
+    /// of `entry`.
+    pub fn new_adding(data: Data, entry: Entry) -> Self {
+        Self::new_only_editor(data, EntryEditor::new_adding(entry))
+    }
+
+    /// Creates an app that only edits an existing entry.
+    pub fn new_editing(data: Data, index: usize) -> Self {
+        let entry = data.entries()[index].clone();
+        Self::new_only_editor(data, EntryEditor::new_editing(entry, index))
+    }
+
+    fn new_only_editor(data: Data, entry_editor: EntryEditor) -> Self {
+        let mut this = Self::new(data, "");
+        this.entry_editor = Some(entry_editor);
+        this.query.blur();
+        this.only_editor = true;
+        this
+    }
+
+    /// Shows the results of the latest query if they are ready, returning
+    /// whether anything changed.
+    pub fn update(&mut self) -> bool {
+        let started = self.worker.is_some();
+        match self.worker().try_recv() {
+            Some(matches) => {
+                self.show_matches(matches);
+                true
+            }
+            None => !started,
         }
     }
 
@@ -58,6 +171,9 @@ impl App {
                 Some(false) => {}
                 None => self.dialog = Some(dialog),
             };
+            if let Some(command) = self.confirmed_command.take() {
+                return Ok(AppControl::Become(command));
+            }
             return AppControl::CONTINUE;
         } else if let Some(entry_editor) = &mut self.entry_editor {
             match entry_editor.read(input) {
@@ -70,12 +186,42 @@ impl App {
                     self.data.borrow_mut().edit(idx, entry)?;
                     self.close_entry_editor();
                 }
+                None => return AppControl::CONTINUE,
+            }
+            if self.only_editor {
+                return AppControl::EXIT;
+            }
+            return AppControl::CONTINUE;
+        } else if let Some(history_search) = &mut self.history_search {
+            match history_search.read(input) {
+                Some(true) => {
+                    let query = history_search.focused().map(str::to_owned);
+                    self.history_search = None;
+                    if let Some(query) = query {
+                        self.set_query(&query);
+                    }
+                }
+                Some(false) => self.history_search = None,
                 None => {}
             }
             return AppControl::CONTINUE;
         }
 
         // main screen
+        let acts_on_matches = matches!(
+            input,
+            Input {
+                key: Key::Enter | Key::Up | Key::Down,
+                ..
+            } | Input {
+                key: Key::Char('e' | 'd'),
+                ctrl: true,
+                ..
+            }
+        );
+        if acts_on_matches {
+            self.wait_for_matches();
+        }
         match input {
             Input { key: Key::Esc, .. } => return AppControl::EXIT,
             Input {
@@ -92,13 +238,39 @@ impl App {
                 key: Key::Char('d'),
                 ctrl: true,
                 ..
-            } => self.set_dialog(
+            } if self.focused_entry_index().is_some() => self.set_dialog(
                 "Are you sure you want to delete this entry?",
                 Self::remove_focused,
             ),
+            // shortcut to add an entry when nothing matches
+            Input {
+                key: Key::Enter, ..
+            } if self.matches.is_empty() => self.add_new(),
             Input {
                 key: Key::Enter, ..
-            } => return Ok(AppControl::Become(self.focused_entry().code)),
+            } => return self.select_focused(),
+            Input {
+                key: Key::Char('s'),
+                ctrl: true,
+                ..
+            } => self.toggle_scope(),
+            Input {
+                key: Key::Char('p'),
+                ctrl: true,
+                ..
+            } => self.previous_query(),
+            // going up from the top of an empty list goes through the history too
+            Input { key: Key::Up, .. } if self.matches.is_empty() => self.previous_query(),
+            Input {
+                key: Key::Char('n'),
+                ctrl: true,
+                ..
+            } => self.next_query(),
+            Input {
+                key: Key::Char('r'),
+                ctrl: true,
+                ..
+            } => self.search_history(),
             Input { key: Key::Down, .. } => self.next_item(),
             Input { key: Key::Up, .. } => self.prev_item(),
             _ => self.register_input(input),
@@ -108,7 +280,8 @@ impl App {
     }
 
     fn next_item(&mut self) {
-        self.list_index = Saturating((self.list_index.0 + 1).min(self.matches.len() - 1))
+        self.list_index =
+            Saturating((self.list_index.0 + 1).min(self.matches.len().saturating_sub(1)))
     }
 
     fn prev_item(&mut self) {
@@ -116,14 +289,119 @@ impl App {
     }
 
     fn register_input(&mut self, ev: Input) {
+        let previous = self.query_text().to_owned();
         self.query.input(ev);
-        self.refresh_list();
+        if self.query_text() != previous {
+            self.history_position = None;
+            let query = self.query_text().to_owned();
+            self.worker().rank(query);
+            self.ranking = true;
+        }
+    }
+
+    /// Replaces the query, ranking it like it was typed.
+    fn set_query(&mut self, query: &str) {
+        self.query.set_text(query);
+        self.worker().rank(query);
+        self.ranking = true;
+    }
+
+    /// Replaces the query with the one before it in the history.
+    fn previous_query(&mut self) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let position = self.history_position.as_ref().map_or(0, |(p, _)| p + 1);
+        let Some(query) = history.recent().nth(position).map(str::to_owned) else {
+            return;
+        };
+        let typed = match self.history_position.take() {
+            Some((_, typed)) => typed,
+            None => self.query_text().to_owned(),
+        };
+        self.set_query(&query);
+        self.history_position = Some((position, typed));
+    }
+
+    /// Replaces the query with the one after it in the history, or the
+    /// query that was typed before going through it.
+    fn next_query(&mut self) {
+        let (Some(history), Some((position, typed))) =
+            (&self.history, self.history_position.take())
+        else {
+            return;
+        };
+        let Some(position) = position.checked_sub(1) else {
+            self.set_query(&typed);
+            return;
+        };
+        let query = history
+            .recent()
+            .nth(position)
+            .expect("the position was already in the history")
+            .to_owned();
+        self.set_query(&query);
+        self.history_position = Some((position, typed));
+    }
+
+    fn search_history(&mut self) {
+        if let Some(history) = &self.history {
+            let queries = history.recent().map(str::to_owned).collect();
+            self.history_search = Some(Picker::new(queries, "Previous queries").single());
+        }
+    }
+
+    /// Waits for the latest query to be ranked, so the focused entry is the
+    /// one that will be shown.
+    fn wait_for_matches(&mut self) {
+        self.worker();
+        if self.ranking {
+            if let Some(matches) = self.worker().recv() {
+                self.show_matches(matches);
+            }
+        }
     }
 
-    fn refresh_list(&mut self) {
-        let borrow = self.data.borrow();
-        self.matches = rank::rank(self.query_text(), borrow.entries());
+    fn show_matches(&mut self, matches: Vec<Match>) {
+        self.matches = matches;
         self.list_index = Saturating(0);
+        self.ranking = false;
+    }
+
+    /// The worker, which is started if it wasn't already.
+    fn worker(&mut self) -> &Worker {
+        if self.worker.is_none() {
+            self.load_searcher();
+        }
+        self.worker.as_ref().expect("the worker was just started")
+    }
+
+    /// Ranks the query again after the entries or options changed, if the
+    /// search was started.
+    fn reload_entries(&mut self) {
+        if self.worker.is_some() {
+            self.load_searcher();
+        }
+    }
+
+    /// Gives the worker a searcher with the current entries and options,
+    /// and ranks the query with it.
+    fn load_searcher(&mut self) {
+        let data = self.data.borrow();
+        let (entries, index) = (data.entries().to_vec(), data.index().clone());
+        drop(data);
+        let (options, environment) = (self.rank_options.clone(), self.environment.clone());
+        let searcher = move || Searcher::new(options, &environment, &entries, &index);
+        let query = self.query_text().to_owned();
+        match &self.worker {
+            Some(worker) => worker.replace_and_rank(searcher, query),
+            None => {
+                let worker = Worker::spawn(searcher);
+                worker.rank(query);
+                self.worker = Some(worker);
+            }
+        }
+        self.ranking = true;
     }
 
     fn set_dialog(
@@ -135,8 +413,17 @@ impl App {
     }
 
     fn remove_focused(&mut self) -> Result<()> {
-        self.data.borrow_mut().remove(self.focused_entry_index())?;
-        self.refresh_list();
+        if let Some(index) = self.focused_entry_index() {
+            self.data.borrow_mut().remove(index)?;
+            // the matches are shown until the worker ranks the entries again
+            self.matches.retain(|m| m.index != index);
+            for m in &mut self.matches {
+                if m.index > index {
+                    m.index -= 1;
+                }
+            }
+            self.reload_entries();
+        }
         Ok(())
     }
 
@@ -147,7 +434,7 @@ impl App {
     fn close_entry_editor(&mut self) {
         self.entry_editor = None;
         self.query.focus();
-        self.refresh_list();
+        self.reload_entries();
     }
 
     fn add_new(&mut self) {
@@ -156,23 +443,125 @@ impl App {
     }
 
     fn edit_focused(&mut self) {
-        let entry = self.focused_entry();
+        let (Some(entry), Some(index)) = (self.focused_entry(), self.focused_entry_index()) else {
+            return;
+        };
 
-        self.entry_editor = Some(EntryEditor::new_editing(
-            entry.title,
-            entry.code,
-            entry.description,
-            self.focused_entry_index(),
-        ));
+        self.entry_editor = Some(EntryEditor::new_editing(entry, index));
         self.query.blur();
     }
 
-    fn focused_entry(&self) -> Entry {
-        self.data.borrow().entries()[self.focused_entry_index()].clone()
+    /// Selects the focused command, first asking for confirmation if it
+    /// is dangerous.
+    fn select_focused(&mut self) -> Result<AppControl> {
+        let (Some(index), Some(command)) = (self.focused_entry_index(), self.focused_command())
+        else {
+            return AppControl::CONTINUE;
+        };
+        let dangerous = self.data.borrow().entries()[index].dangerous;
+        if !self.confirm_dangerous || !(dangerous || danger::is_dangerous(&command)) {
+            self.record_use(index)?;
+            return Ok(AppControl::Become(command));
+        }
+
+        self.set_dialog(
+            format!("This command may be destructive, run it anyway?\n\n{command}"),
+            move |app| {
+                app.record_use(index)?;
+                app.confirmed_command = Some(command);
+                Ok(())
+            },
+        );
+        AppControl::CONTINUE
+    }
+
+    /// Records that an entry was used in the current directory.
+    fn record_use(&mut self, index: usize) -> Result<()> {
+        let usage = Usage::now(self.environment.dir());
+        self.data.borrow_mut().record_use(index, usage)
+    }
+
+    /// Switches between searching every entry and only the ones used in
+    /// the current directory.
+    fn toggle_scope(&mut self) {
+        self.rank_options.scope = self.rank_options.scope.toggle();
+        self.query.set_title(scope_title(self.rank_options.scope));
+        self.reload_entries();
+    }
+
+    /// The code of the focused entry, with inputs filled in if it's a
+    /// template.
+    ///
+    /// Code that isn't a valid template is used as is.
+    fn focused_command(&self) -> Option<String> {
+        let entry = self.focused_entry()?;
+        if !entry.template {
+            return Some(entry.code);
+        }
+        Some(match template::parse(&entry.code) {
+            Ok(template) => template.fill_defaults(self.quoting),
+            Err(_) => entry.code,
+        })
+    }
+
+    fn focused_entry(&self) -> Option<Entry> {
+        let index = self.focused_entry_index()?;
+        Some(self.data.borrow().entries()[index].clone())
+    }
+
+    /// Index of the focused entry, or `None` if nothing matches.
+    fn focused_entry_index(&self) -> Option<usize> {
+        self.matches.get(self.list_index.0).map(|m| m.index)
+    }
+
+    /// Panel shown instead of an entry when there are no matches.
+    fn empty_panel(&self) -> Paragraph<'static> {
+        let text = if self.data.borrow().entries().is_empty() {
+            let mut text = vec![
+                line!["Welcome to how!".bold()],
+                line![],
+                line!["There are no saved commands yet."],
+                line![],
+                line!["Ctrl-A".bold(), ": add the first entry"],
+                line![],
+                line!["Or import commands from another format:"],
+            ];
+            text.extend(import::Format::value_variants().iter().map(|format| {
+                let name = format.to_possible_value().expect("no skipped formats");
+                // history is read from the shell's history file by default
+                let path = if format.is_history() {
+                    "[path]"
+                } else {
+                    "<path>"
+                };
+                line![format!("  how import --format {} {path}", name.get_name()).dark_gray()]
+            }));
+            text
+        } else {
+            let mut text = vec![
+                line!["No matches.".bold()],
+                line![],
+                line![
+                    "Enter".bold(),
+                    format!(": add \"{}\" as a new entry", self.query_text())
+                ],
+            ];
+            if self.rank_options.scope == Scope::Directory {
+                text.push(line!["Ctrl-S".bold(), ": search entries used anywhere"]);
+            }
+            text
+        };
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered())
     }
+}
 
-    fn focused_entry_index(&self) -> usize {
-        self.matches[self.list_index.0].0
+/// Title of the search box, saying which entries are searched.
+fn scope_title(scope: Scope) -> &'static str {
+    match scope {
+        Scope::Global => "Search",
+        Scope::Directory => "Search in this directory",
     }
 }
 
@@ -181,17 +570,45 @@ impl Widget for &App {
     where
         Self: Sized,
     {
-        let (query_area, list_area, pane_area) = {
+        if let (true, Some(entry_editor)) = (self.only_editor, &self.entry_editor) {
+            entry_editor.render(area, buf);
+            return;
+        }
+
+        let (search_area, query_area, list_area, pane_area) = {
             let hor = horizontal![==1/2; 2].split(area);
             let vert = vertical![==3, *=1].split(hor[0]);
-            (vert[0], vert[1], hor[1])
+            (hor[0], vert[0], vert[1], hor[1])
         };
         let data = Rc::clone(&self.data);
         let matches = self.matches.clone();
 
         let builder = ListBuilder::new(move |cx| {
-            let item = data.borrow().entries()[matches[cx.index].0].clone();
-            let title = line![item.title, format!(" ({:.4})", matches[cx.index].1)];
+            let Match {
+                index,
+                score,
+                positions,
+                in_context,
+            } = &matches[cx.index];
+            let item = data.borrow().entries()[*index].clone();
+            let marker = if item.dangerous || danger::is_dangerous(&item.code) {
+                "! ".red()
+            } else {
+                "".into()
+            };
+            let context_marker = if *in_context {
+                "• ".green()
+            } else {
+                "".into()
+            };
+            // only the first line of a title fits in the list
+            let mut lines = highlight(&item.title, &positions.title).lines.into_iter();
+            let mut title = lines.next().unwrap_or_default();
+            if lines.next().is_some() {
+                title.spans.push(" …".into());
+            }
+            title.spans.splice(0..0, [marker, context_marker]);
+            title.spans.push(format!(" ({score:.4})").into());
             let title = if cx.is_selected {
                 title.on_dark_gray().bold().yellow()
             } else {
@@ -203,17 +620,27 @@ impl Widget for &App {
         let list = ListView::new(builder, self.matches.len());
 
         let mut list_state = ListState::default();
-        list_state.select(Some(self.list_index.0));
+        list_state.select((!self.matches.is_empty()).then_some(self.list_index.0));
 
-        self.query.render(query_area, buf);
-        list.render(list_area, buf, &mut list_state);
+        if let Some(history_search) = &self.history_search {
+            history_search.render(search_area, buf);
+        } else {
+            self.query.render(query_area, buf);
+            list.render(list_area, buf, &mut list_state);
+        }
 
         if let Some(entry_editor) = &self.entry_editor {
             entry_editor.render(pane_area, buf);
+        } else if let (Some(selected), Some(m)) =
+            (self.focused_entry(), self.matches.get(self.list_index.0))
+        {
+            EntryPreview {
+                entry: &selected,
+                positions: &m.positions,
+            }
+            .render(pane_area, buf);
         } else {
-            let binding = self.data.borrow();
-            let selected = binding.entries()[self.matches[self.list_index.0].0].clone();
-            selected.render(pane_area, buf);
+            self.empty_panel().render(pane_area, buf);
         }
 
         if let Some(dialog) = &self.dialog {
@@ -240,6 +667,8 @@ struct EntryEditor {
     description: TextArea,
     focus: Wrapping<3>,
     kind: EditorKind,
+    /// Entry to take the fields that aren't editable from.
+    base: Entry,
 }
 
 impl EntryEditor {
@@ -256,16 +685,18 @@ impl EntryEditor {
             description: TextArea::new_blurred(description, "Description"),
             focus: Wrapping::default(),
             kind: EditorKind::Adding,
+            base: Entry::new("", "", ""),
         }
     }
 
-    pub fn new_editing(
-        title: impl Into<String>,
-        code: impl Into<String>,
-        description: impl Into<String>,
-        entry_index: usize,
-    ) -> Self {
-        let mut this = Self::new(title, code, description);
+    pub fn new_adding(entry: Entry) -> Self {
+        let mut this = Self::new(&*entry.title, &*entry.code, &*entry.description);
+        this.base = entry;
+        this
+    }
+
+    pub fn new_editing(entry: Entry, entry_index: usize) -> Self {
+        let mut this = Self::new_adding(entry);
         this.kind = EditorKind::Editing(entry_index);
         this
     }
@@ -284,6 +715,16 @@ impl EntryEditor {
                 ..
             } => self.focus_prev(),
             Input { key: Key::Esc, .. } => return Some(Action::Exit),
+            Input {
+                key: Key::Char('t'),
+                ctrl: true,
+                ..
+            } => self.base.template = !self.base.template,
+            Input {
+                key: Key::Char('g'),
+                ctrl: true,
+                ..
+            } => self.base.dangerous = !self.base.dangerous,
             Input {
                 key: Key::Char('s'),
                 ctrl: true,
@@ -336,19 +777,227 @@ impl Widget for &EntryEditor {
         let title_height = cmp::max(1, self.title.lines().len() as u16) + 2;
         let code_height = cmp::max(1, self.code.lines().len() as u16) + 2;
 
-        let layout = vertical![==title_height, ==code_height, *=1].split(area);
+        let layout = vertical![==title_height, ==code_height, *=1, ==1].split(area);
         self.title.render(layout[0], buf);
         self.code.render(layout[1], buf);
         self.description.render(layout[2], buf);
+
+        let check = |checked| if checked { "[x]" } else { "[ ]" };
+        format!(
+            " {} Ctrl-T: fill inputs like [default#description]  {} Ctrl-G: always confirm",
+            check(self.base.template),
+            check(self.base.dangerous)
+        )
+        .dark_gray()
+        .render(layout[3], buf);
     }
 }
 
 impl From<&EntryEditor> for Entry {
     fn from(value: &EntryEditor) -> Self {
-        Self::new(
-            value.title.text(),
-            value.code.text(),
-            value.description.text(),
-        )
+        Self {
+            title: value.title.text(),
+            code: value.code.text(),
+            description: value.description.text(),
+            ..value.base.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+    use tui_textarea::{Input, Key};
+
+    use super::{App, AppControl};
+    use crate::{
+        context::Environment,
+        db::Entry,
+        rank,
+        utils::fixtures::{empty_data, open_history, temp_path},
+    };
+
+    fn key(key: Key, ctrl: bool) -> Input {
+        Input {
+            key,
+            ctrl,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    /// The rendered text, with a line for each row.
+    fn screen(buf: &Buffer) -> String {
+        buf.content()
+            .chunks(buf.area.width.into())
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn empty_database() {
+        let mut app = App::new(empty_data("empty"), "docker");
+        let area = Rect::new(0, 0, 100, 30);
+        let mut buf = Buffer::empty(area);
+        (&app).render(area, &mut buf);
+        let screen = screen(&buf);
+        assert!(screen.contains("how import --format navi <path>"));
+        assert!(screen.contains("how import --format zsh-history [path]"));
+
+        for input in [
+            key(Key::Down, false),
+            key(Key::Up, false),
+            key(Key::Char('e'), true),
+            key(Key::Char('d'), true),
+        ] {
+            assert!(matches!(app.read(input).unwrap(), AppControl::Continue));
+            (&app).render(area, &mut buf);
+        }
+        assert!(app.entry_editor.is_none() && app.dialog.is_none());
+
+        // enter adds an entry from the query
+        assert!(matches!(
+            app.read(key(Key::Enter, false)).unwrap(),
+            AppControl::Continue
+        ));
+        assert!(app.entry_editor.is_some());
+        (&app).render(area, &mut buf);
+    }
+
+    #[test]
+    fn ranks_once_configured() {
+        let mut data = empty_data("configured");
+        data.add(Entry::new("list files", "ls", "")).unwrap();
+        let mut app = App::new(data, "ls")
+            .set_rank_options(rank::Options::default())
+            .set_environment(Environment::at(env::temp_dir()));
+        assert!(app.worker.is_none() && app.matches.is_empty());
+        assert!(app.update());
+        app.wait_for_matches();
+        assert_eq!(app.matches.len(), 1);
+
+        // the editor never searches
+        let mut app = App::new_adding(empty_data("configured-editor"), Entry::new("", "ls", ""));
+        app.read(key(Key::Char('l'), false)).unwrap();
+        assert!(app.worker.is_none());
+    }
+
+    #[test]
+    fn directory_scope() {
+        let mut data = empty_data("scope");
+        data.add_all([
+            Entry::new("list files", "ls", ""),
+            Entry::new("git status", "git status", ""),
+        ])
+        .unwrap();
+        let mut app = App::new(data, "status").set_environment(Environment::at(env::temp_dir()));
+        assert!(matches!(
+            app.read(key(Key::Enter, false)).unwrap(),
+            AppControl::Become(command) if command == "git status"
+        ));
+        let entry = app.data.borrow().entries()[1].clone();
+        assert_eq!((entry.used, entry.history.len()), (1, 1));
+
+        // only the entry that was used here is left
+        for _ in "status".chars() {
+            app.read(key(Key::Backspace, false)).unwrap();
+        }
+        app.read(key(Key::Char('s'), true)).unwrap();
+        app.wait_for_matches();
+        assert_eq!(app.matches.len(), 1);
+        assert_eq!(app.matches[0].index, 1);
+        app.read(key(Key::Char('s'), true)).unwrap();
+        app.wait_for_matches();
+        assert_eq!(app.matches.len(), 2);
+    }
+
+    #[test]
+    fn shows_first_line_of_titles() {
+        let mut data = empty_data("multi-line");
+        data.add(Entry::new("build\nand test", "make test", ""))
+            .unwrap();
+        let mut app = App::new(data, "build");
+        app.wait_for_matches();
+        let area = Rect::new(0, 0, 100, 30);
+        let mut buf = Buffer::empty(area);
+        (&app).render(area, &mut buf);
+        assert!(screen(&buf).lines().any(|l| l.contains("build …")));
+    }
+
+    #[test]
+    fn fills_only_templates() {
+        let mut data = empty_data("templates");
+        data.add_all([
+            Entry::new("digits", "grep '[0-9]' file", ""),
+            Entry::new("array", "echo ${arr[0]}", ""),
+            Entry::new("source", "cd [~/src#dir] && ls [my dir]", "").as_template(),
+        ])
+        .unwrap();
+        let mut app = App::new(data, "");
+        let mut command = |query: &str| {
+            app.set_query(query);
+            match app.read(key(Key::Enter, false)).unwrap() {
+                AppControl::Become(command) => command,
+                _ => panic!("{query} wasn't selected"),
+            }
+        };
+        assert_eq!(command("digits"), "grep '[0-9]' file");
+        assert_eq!(command("array"), "echo ${arr[0]}");
+        assert_eq!(command("source"), "cd ~/src && ls 'my dir'");
+    }
+
+    #[test]
+    fn editor_toggles_template() {
+        for (entry, template) in [
+            (Entry::new("", "cd [dir]", ""), true),
+            (Entry::new("", "cd [dir]", "").as_template(), false),
+        ] {
+            let mut app = App::new_adding(empty_data("toggle-template"), entry);
+            for c in "cd".chars() {
+                app.read(key(Key::Char(c), false)).unwrap();
+            }
+            app.read(key(Key::Char('t'), true)).unwrap();
+            app.read(key(Key::Char('s'), true)).unwrap();
+            let entry = app.data.borrow().entries()[0].clone();
+            assert_eq!((&*entry.title, entry.template), ("cd", template));
+        }
+    }
+
+    #[test]
+    fn editor_toggles_dangerous() {
+        let mut data = empty_data("toggle-dangerous");
+        data.add(Entry::new("wipe", "rm -rf build", "")).unwrap();
+        let mut app = App::new_editing(data, 0);
+        app.read(key(Key::Char('g'), true)).unwrap();
+        app.read(key(Key::Char('s'), true)).unwrap();
+        assert!(app.data.borrow().entries()[0].dangerous);
+    }
+
+    #[test]
+    fn query_history() {
+        let path = temp_path("query-history");
+        fs::write(&path, "git\ndocker\n").unwrap();
+        let mut app = App::new(empty_data("history"), "ta").set_history(open_history(&path));
+
+        let mut read = |input| {
+            app.read(input).unwrap();
+            app.query_text().to_owned()
+        };
+        assert_eq!(read(key(Key::Char('p'), true)), "docker");
+        assert_eq!(read(key(Key::Up, false)), "git");
+        assert_eq!(read(key(Key::Char('p'), true)), "git");
+        assert_eq!(read(key(Key::Char('n'), true)), "docker");
+        assert_eq!(read(key(Key::Char('n'), true)), "ta");
+        assert_eq!(read(key(Key::Char('n'), true)), "ta");
+
+        read(key(Key::Char('r'), true));
+        read(key(Key::Char('i'), false));
+        assert_eq!(read(key(Key::Enter, false)), "git");
+
+        app.record_query().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "docker\ngit\n");
     }
 }
@@ -34,3 +34,44 @@ impl<const SIZE: u32> Default for Wrapping<SIZE> {
         Self::new(0)
     }
 }
+
+/// Files that tests read and write.
+#[cfg(test)]
+pub mod fixtures {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    use crate::{db::Data, history::QueryHistory};
+
+    /// A path in the temporary directory, unique to the tests' process.
+    pub fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("how-test-{}-{name}", process::id()))
+    }
+
+    /// Data without entries, in a new file.
+    pub fn empty_data(name: &str) -> Data {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(temp_path(&format!("{name}.toml")))
+            .unwrap();
+        Data::load_from(file).unwrap()
+    }
+
+    /// The query history in a file, keeping the queries already in it.
+    pub fn open_history(path: &Path) -> QueryHistory {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .unwrap();
+        QueryHistory::load_from(file).unwrap()
+    }
+}
@@ -2,3 +2,7 @@ mod text_area;
 pub use text_area::TextArea;
 mod confirmation;
 pub use confirmation::ConfirmDialog;
+mod picker;
+pub use picker::Picker;
+mod highlight;
+pub use highlight::highlight;
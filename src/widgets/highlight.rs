@@ -0,0 +1,70 @@
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+};
+
+/// Splits text into lines with the characters at `positions` highlighted.
+///
+/// `positions` are indexes of characters in the whole text, including
+/// newlines, and must be sorted.
+pub fn highlight(text: &str, positions: &[usize]) -> Text<'static> {
+    let style = Style::new().cyan().bold();
+    let mut positions = positions.iter().copied().peekable();
+    let mut offset = 0;
+    let mut lines = Vec::new();
+
+    for line in text.split('\n') {
+        let mut spans = Vec::new();
+        let mut current = String::new();
+        let mut highlighted = false;
+        for (i, c) in line.chars().enumerate() {
+            let is_match = positions.next_if_eq(&(offset + i)).is_some();
+            if is_match != highlighted && !current.is_empty() {
+                let content = std::mem::take(&mut current);
+                spans.push(if highlighted {
+                    Span::styled(content, style)
+                } else {
+                    Span::raw(content)
+                });
+            }
+            highlighted = is_match;
+            current.push(c);
+        }
+        if !current.is_empty() {
+            spans.push(if highlighted {
+                Span::styled(current, style)
+            } else {
+                Span::raw(current)
+            });
+        }
+        lines.push(Line::from(spans));
+
+        // +1 for the newline
+        offset += line.chars().count() + 1;
+        while positions.next_if(|&p| p < offset).is_some() {}
+    }
+
+    Text::from(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::highlight;
+
+    #[test]
+    fn highlights() {
+        let text = highlight("git status\nls", &[4, 5, 11]);
+        assert_eq!(text.lines.len(), 2);
+        let spans = text.lines[0]
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(spans, ["git ", "st", "atus"]);
+        assert_eq!(
+            text.lines[0].spans[1].style.fg,
+            Some(ratatui::style::Color::Cyan)
+        );
+        assert_eq!(text.lines[1].spans[0].content, "l");
+    }
+}
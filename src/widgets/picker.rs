@@ -0,0 +1,183 @@
+use std::num::Saturating;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Block, StatefulWidget, Widget},
+};
+use ratatui_macros::vertical;
+use tui_textarea::{Input, Key};
+use tui_widget_list::{ListBuilder, ListState, ListView};
+
+use super::TextArea;
+
+/// A filterable list where multiple items can be selected, or where only
+/// the focused item is picked.
+pub struct Picker {
+    items: Vec<String>,
+    /// Whether each item is selected, or `None` if only the focused item
+    /// can be picked.
+    selected: Option<Vec<bool>>,
+    filter: TextArea,
+    /// Indexes of `items` that match the filter.
+    visible: Vec<usize>,
+    list_index: Saturating<usize>,
+    title: &'static str,
+}
+
+impl Picker {
+    pub fn new(items: Vec<String>, title: &'static str) -> Self {
+        Self {
+            selected: Some(vec![false; items.len()]),
+            visible: (0..items.len()).collect(),
+            items,
+            filter: TextArea::new_focused("", "Filter").set_single_line(),
+            list_index: Saturating(0),
+            title,
+        }
+    }
+
+    /// Only lets the focused item be picked, like Ctrl-R in a shell.
+    pub fn single(mut self) -> Self {
+        self.selected = None;
+        self
+    }
+
+    /// Reads an input and returns whether they confirm `Some(true)`,
+    /// cancel `Some(false)`, or enter a key that does nothing `None`.
+    pub fn read(&mut self, input: impl Into<Input>) -> Option<bool> {
+        match input.into() {
+            Input {
+                key: Key::Enter, ..
+            } => return Some(true),
+            Input { key: Key::Esc, .. } => return Some(false),
+            Input { key: Key::Tab, .. } if self.selected.is_some() => {
+                self.toggle_focused();
+                self.next_item();
+            }
+            Input {
+                key: Key::Char('a'),
+                ctrl: true,
+                ..
+            } if self.selected.is_some() => self.toggle_all(),
+            // the next match, like pressing Ctrl-R again in a shell
+            Input {
+                key: Key::Char('r'),
+                ctrl: true,
+                ..
+            } if self.selected.is_none() => self.next_item(),
+            Input { key: Key::Down, .. } => self.next_item(),
+            Input { key: Key::Up, .. } => self.prev_item(),
+            input => {
+                self.filter.input(input);
+                self.refresh_list();
+            }
+        }
+        None
+    }
+
+    /// Indexes of all the selected items.
+    pub fn selected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.selected
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(i, selected)| selected.then_some(i))
+    }
+
+    /// The focused item, or `None` if none match the filter.
+    pub fn focused(&self) -> Option<&str> {
+        let &i = self.visible.get(self.list_index.0)?;
+        Some(&self.items[i])
+    }
+
+    fn next_item(&mut self) {
+        self.list_index =
+            Saturating((self.list_index.0 + 1).min(self.visible.len().saturating_sub(1)))
+    }
+
+    fn prev_item(&mut self) {
+        self.list_index -= 1
+    }
+
+    fn toggle_focused(&mut self) {
+        if let (Some(selected), Some(&i)) =
+            (&mut self.selected, self.visible.get(self.list_index.0))
+        {
+            selected[i] = !selected[i];
+        }
+    }
+
+    /// Selects all visible items, or deselects them if they are all
+    /// already selected.
+    fn toggle_all(&mut self) {
+        let Some(selected) = &mut self.selected else {
+            return;
+        };
+        let all_selected = self.visible.iter().all(|&i| selected[i]);
+        for &i in &self.visible {
+            selected[i] = !all_selected;
+        }
+    }
+
+    fn refresh_list(&mut self) {
+        let filter = self.filter.text().to_lowercase();
+        self.visible = (0..self.items.len())
+            .filter(|&i| self.items[i].to_lowercase().contains(&filter))
+            .collect();
+        self.list_index = Saturating(0);
+    }
+}
+
+impl Widget for &Picker {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let [filter_area, list_area, help_area] = vertical![==3, *=1, ==1].areas(area);
+        let block = Block::bordered().title(self.title);
+
+        let items = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let mut lines = self.items[i].lines();
+                let first = lines.next().unwrap_or_default();
+                let more = if lines.next().is_some() { " …" } else { "" };
+                let check = match &self.selected {
+                    Some(selected) if selected[i] => "[x] ",
+                    Some(_) => "[ ] ",
+                    None => "",
+                };
+                Line::from(format!("{check}{first}{more}"))
+            })
+            .collect::<Vec<_>>();
+        let count = items.len();
+        let builder = ListBuilder::new(move |cx| {
+            let item = items[cx.index].clone();
+            let item = if cx.is_selected {
+                item.on_dark_gray().bold().yellow()
+            } else {
+                item
+            };
+            (item, 1)
+        });
+        let list = ListView::new(builder, count);
+        let mut list_state = ListState::default();
+        list_state.select((count > 0).then_some(self.list_index.0));
+
+        self.filter.render(filter_area, buf);
+        list.render(block.inner(list_area), buf, &mut list_state);
+        block.render(list_area, buf);
+        let help = match self.selected {
+            Some(_) => format!(
+                " {} selected. Tab: toggle, Ctrl-A: toggle all, Enter: confirm, Esc: cancel",
+                self.selected().count()
+            ),
+            None => " Ctrl-R: next, Enter: confirm, Esc: cancel".to_string(),
+        };
+        help.dark_gray().render(help_area, buf);
+    }
+}
@@ -127,6 +127,18 @@ impl TextArea {
         self.update_validation();
     }
 
+    /// Replaces the text, moving the cursor to its end.
+    pub fn set_text(&mut self, text: &str) {
+        let mut inner = TuiTextArea::from(text.lines());
+        if let Some(block) = self.inner.block() {
+            inner.set_block(block.clone());
+        }
+        inner.set_cursor_style(self.inner.cursor_style());
+        inner.move_cursor(CursorMove::End);
+        self.inner = inner;
+        self.update_validation();
+    }
+
     // regular delegated methods //
 
     pub fn lines(&self) -> &[String] {